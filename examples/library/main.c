@@ -0,0 +1,13 @@
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+
+uint32_t add_twice(uint32_t value, uint32_t step);
+bool is_small(uint32_t value);
+
+// expect: 7 small
+int main(void) {
+    uint32_t value = add_twice(1, 3);
+    printf("%u %s\n", value, is_small(value) ? "small" : "large");
+    return 0;
+}
@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use crate::ast::{AstLiteral, Block, Const, Expr, Expression, Func, Ident, Item, Module, Op, Operator, Statement, Tag, TagValue, Ty, Type};
+use crate::ast::{AstLiteral, Block, Const, Expr, Expression, Func, Ident, Import, Item, Module, Op, Operator, Statement, Tag, TagValue, Ty, Type};
 use crate::tokens::{Literal, NumLit};
 
 pub(crate) trait CodePrinter{
@@ -72,8 +72,16 @@ impl CodePrinter for AstLiteral {
 }
 
 impl CodePrinter for HashMap<String, Tag> {
+    /// The doc comment goes first, other tags are written as `#[...]`.
     fn print(&self) -> String {
-        self.iter().map(|(_name, tag)| format!("#[{}]", tag.print())).collect::<Vec<_>>().join("\n")
+        let doc = self.get("doc").and_then(|doc| doc.1.iter().map(|line| match line {
+            TagValue::Lit(AstLiteral(Literal::String(line), _)) if line.is_empty() => Some("///".to_string()),
+            TagValue::Lit(AstLiteral(Literal::String(line), _)) => Some(format!("/// {line}")),
+            _ => None
+        }).collect::<Option<Vec<_>>>());
+        doc.iter().flatten().cloned()
+            .chain(self.iter().filter(|(name, _)| doc.is_none() || *name != "doc").map(|(_name, tag)| format!("#[{}]", tag.print())))
+            .collect::<Vec<_>>().join("\n")
     }
 }
 
@@ -119,6 +127,7 @@ impl CodePrinter for Expression {
             Expr::VarAssign(ident, None, expr) => format!("{} = {};", ident.print(), expr.print()),
             Expr::Block(block) => block.print_indented(),
             Expr::If(cond, body, else_body) => format!("if {} {} else {}", cond.print(), body.print(), else_body.print()),
+            Expr::Loop() => "loop {}".to_string(),
             Expr::Return(expr) => match expr { Some(e) => format!("return {}", e.print()), None => format!("return") }
         })
     }
@@ -174,7 +183,19 @@ impl CodePrinter for Func {
 
 impl CodePrinter for Const {
     fn print(&self) -> String {
-        format!("const {}: {} = {};", self.name.print(), self.ty.print(), self.val.print())
+        format!("{}const {}: {} = {};",
+            if self.tags.len() > 0 { format!("{}\n", self.tags.print()) } else { String::new() },
+            self.name.print(), self.ty.print(), self.val.print())
+    }
+}
+
+impl CodePrinter for Import {
+    fn print(&self) -> String {
+        if self.path.0.last().map(|i| i.0 == self.name.0).unwrap_or(false) {
+            format!("use {};", self.path.print())
+        } else {
+            format!("use {} as {};", self.path.print(), self.name.print())
+        }
     }
 }
 
@@ -190,14 +211,18 @@ impl CodePrinter for Block {
 
 impl CodePrinter for Module {
     fn print(&self) -> String {
-        format!("mod {} {{\n    {}\n}}", self.name.print(), self.print_content().replace("\n", "\n    "))
+        format!("{}mod {} {{\n    {}\n}}",
+            if self.tags.len() > 0 { format!("{}\n", self.tags.print()) } else { String::new() },
+            self.name.print(), self.print_content().replace("\n", "\n    "))
     }
 }
 
 impl Module {
     fn print_content(&self) -> String {
-        format!("{}\n\n{}",
+        format!("{}\n\n{}\n\n{}\n\n{}",
+                self.imports.values().map(|i| i.print()).collect::<Vec<_>>().join("\n"),
                 self.constants.values().map(|c| c.print()).collect::<Vec<_>>().join("\n\n"),
-                self.functions.values().map(|t| t.print()).collect::<Vec<_>>().join("\n\n"))
+                self.functions.values().map(|t| t.print()).collect::<Vec<_>>().join("\n\n"),
+                self.sub_modules.values().map(|m| m.print()).collect::<Vec<_>>().join("\n\n"))
     }
 }
\ No newline at end of file
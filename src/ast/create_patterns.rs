@@ -1,15 +1,27 @@
 use std::collections::HashMap;
-use crate::ast::{Block, Expr, Expression, Type, Func, Item, Statement, Ty, Const, AstLiteral, TagValue, Tag, Operator, Op};
+use crate::ast::{Block, Expr, Expression, Type, Func, Item, Statement, Ty, Const, AstLiteral, TagValue, Tag, Operator, Op, Ident, Import};
 use crate::ast::patterns::{Consumer, Pat, Pattern};
-use crate::ast::patterns::conditional::{While, Match, Succeed, Fail, IsOk, Optional, Or, Both};
+use crate::ast::patterns::conditional::{While, Match, Succeed, Fail, IsOk, Optional, Or, Both, Recover};
 use crate::ast::patterns::dynamic::{Latent, Mapping};
-use crate::ast::patterns::simple::{ExpectIdent, ExpectParticle, ExpectParticleExact, GetGluedParticle, GetIdent, GetLiteral, GetNext, GetParticle};
+use crate::ast::patterns::simple::{ExpectIdent, ExpectParticle, ExpectParticleExact, GetGluedParticle, GetIdent, GetDoc, GetLiteral, GetNext, GetParticle, SkipUntil};
 use crate::error::{LithiaET};
 use crate::source::span::Span;
-use crate::tokens::{Literal, NumLit, NumLitTy};
+use crate::tokens::{Literal, NumLit, NumLitTy, TokenType};
+
+/// A `mod` declaration, either with its content inline or to be loaded from its own file.
+pub(crate) enum ModDecl {
+    Inline(Ident, ModuleItems, Span),
+    External(Ident)
+}
+
+/// The `//!` doc of the module, its items and its sub modules with their doc.
+pub(crate) type ModuleItems = (HashMap<String, Tag>, HashMap<String, Func>, HashMap<String, Const>, HashMap<String, Import>, Vec<(HashMap<String, Tag>, ModDecl)>);
 
 pub(crate) struct Patterns{
-    pub(crate) module_content: Pat<((HashMap<String, Func>, HashMap<String, Const>), Span)>
+    pub(crate) module_content: Pat<(ModuleItems, Span)>,
+    /// a single item, parsed on its own to report what was expected instead of tokens after the module content
+    pub(crate) module_item: Pat<()>,
+    pub(crate) expression: Pat<Expression>
 }
 
 pub(crate) fn build_patterns() -> Patterns {
@@ -80,27 +92,49 @@ pub(crate) fn build_patterns() -> Patterns {
                                     While(ExpectParticle('#').pat(), full_tag.clone()),
                                     |tags, _| tags.into_iter().map(|tag| (tag
                                                                               .0.0.clone(), tag)).collect::<HashMap<String, Tag>>());
-    let operator = Pattern::named("operator", (
-        GetParticle, Optional(Both(GetGluedParticle.pat(), Fail(ExpectParticleExact('=', true).pat()).pat()).pat(), GetGluedParticle.pat())
-    ).map_res(|((p1, _), p2), loc| Ok(match p2.map(|c| p1.to_string() + &c.to_string()).unwrap_or(p1.to_string()).as_str() {
-        "+" => Op::Add,
-        "-" => Op::Sub,
-        "*" => Op::Mul,
-        "/" => Op::Div,
-        "&" => Op::And,
-        "|" => Op::Or,
-        "&&" => Op::BinAnd,
-        "||" => Op::BinOr,
-        "<<" => Op::LShift,
-        ">>" => Op::RShift,
-        "<" => Op::LT,
-        "<=" => Op::LE,
-        ">" => Op::GT,
-        ">=" => Op::GE,
-        "==" => Op::EQ,
-        "!=" => Op::NE,
-        invalid => return Err(LithiaET::ParsingError(format!("invalid op {invalid}")).at(loc))
-    })), |op, loc| Operator(op, loc));
+    // items also take `///` doc comments, each line is a tag and they are joined into a single `doc` tag
+    let doc = Pattern::inline(GetDoc(false), |line, loc| Tag::doc(vec![line], loc));
+    let item_tags = Pattern::named("tags",
+                                    While(Match(vec![
+                                        (ExpectParticle('#').pat(), ().pat()),
+                                        (GetDoc(false).map(|_, _| ()).pat(), ().pat())
+                                    ]).pat(), Match(vec![
+                                        (ExpectParticle('#').pat(), full_tag.clone()),
+                                        (GetDoc(false).map(|_, _| ()).pat(), doc)
+                                    ]).pat()),
+                                    |tags, _| {
+                                        let mut map = HashMap::new();
+                                        tags.into_iter().for_each(|tag| tag.insert_into(&mut map));
+                                        map
+                                    });
+    // a glued `=` otherwise starts the assignment of `op=`, so comparisons are matched first
+    let comparison = (GetParticle, ExpectParticleExact('=', true)).map_res(|((p, _), _), loc| Ok(match p {
+        '<' => Op::LE,
+        '>' => Op::GE,
+        '=' => Op::EQ,
+        '!' => Op::NE,
+        invalid => return Err(LithiaET::ParsingError(format!("invalid op {invalid}=")).at(loc))
+    })).pat();
+    let operator = Pattern::named("operator", Match(vec![
+        (Succeed(comparison.clone()).pat(), comparison),
+        (().pat(), (
+            GetParticle, Optional(Both(GetGluedParticle.pat(), Fail(ExpectParticleExact('=', true).pat()).pat()).pat(), GetGluedParticle.pat())
+        ).map_res(|((p1, _), p2), loc| Ok(match p2.map(|c| p1.to_string() + &c.to_string()).unwrap_or(p1.to_string()).as_str() {
+            "+" => Op::Add,
+            "-" => Op::Sub,
+            "*" => Op::Mul,
+            "/" => Op::Div,
+            "&" => Op::And,
+            "|" => Op::Or,
+            "&&" => Op::BinAnd,
+            "||" => Op::BinOr,
+            "<<" => Op::LShift,
+            ">>" => Op::RShift,
+            "<" => Op::LT,
+            ">" => Op::GT,
+            invalid => return Err(LithiaET::ParsingError(format!("invalid op {invalid}")).at(loc))
+        })).pat())
+    ]), |op, loc| Operator(op, loc));
     let (expression, expression_finalizer) = Latent::new();
     let let_create = Pattern::named("variable creation", (
         ExpectIdent("let".to_string()),
@@ -128,11 +162,12 @@ pub(crate) fn build_patterns() -> Patterns {
             expression.clone(),
             IsOk(ExpectParticle(';').pat())
         ), |(expr, terminated), loc| Statement(expr, terminated, loc));
+    // a statement that fails to parse is skipped up to the next `;`
     let block_content = Pattern::named("block",
         While(
             Fail(ExpectParticle('}').pat()).pat(),
-            statement.clone()
-        ), |stmts, loc| Block(stmts, loc));
+            Recover(statement.clone(), SkipUntil(|tt| matches!(tt, TokenType::Particle(';', _)), true).pat()).pat()
+        ), |stmts, loc| Block(stmts.into_iter().flatten().collect(), loc));
     let block = Pattern::inline(    (
             ExpectParticle('{'), block_content.clone(), ExpectParticle('}')
         ), |(_, block, _), _| block);
@@ -225,51 +260,146 @@ pub(crate) fn build_patterns() -> Patterns {
         ExpectParticle('='),
         expression.clone(),
         ExpectParticle(';'),
-        ), |(_, name, _, ty, _, val, _), loc| Const { name, ty, val });
+        ), |(_, name, _, ty, _, val, _), loc| Const { tags: HashMap::new(), name, ty, val });
     enum ModuleContent{
         Function(Func),
-        Const(Const)
+        Const(Const),
+        Use(Vec<Import>),
+        Module(ModDecl)
     }
-    let module_content = Pattern::named("module content",
-        While(
-        GetNext.pat(),
-        (tags.clone(),
-         Match(vec![
+    let (use_tree, use_tree_finalizer) = Latent::new();
+    use_tree_finalizer.finalize(Pattern::named("use tree", (
+        item.clone(),
+        Optional(
+            (ExpectParticle(':'), ExpectParticleExact(':', true), ExpectParticle('{')).pat(),
+            (ExpectParticle(':'), ExpectParticleExact(':', true), ExpectParticle('{'),
+             While(
+                 Fail(ExpectParticle('}').pat()).pat(),
+                 (use_tree.clone(), Optional(ExpectParticle(',').pat(), ExpectParticle(',').pat())).map(|(tree, _), _| tree).pat()
+             ),
+             ExpectParticle('}')).map(|(_, _, _, trees, _), _| trees).pat()
+        ),
+        Optional(ExpectIdent("as".to_string()).pat(), (ExpectIdent("as".to_string()), GetIdent).map(|(_, alias), _| alias).pat())
+    ), |t, _| t).map_res(|(path, group, alias), loc| Ok(match (group, alias) {
+        (Some(_), Some(_)) => return Err(LithiaET::ParsingError("a group of imports cannot be renamed".to_string()).at(loc)),
+        (Some(group), None) => group.into_iter().flatten().map(|Import { name, path: Item(segments, loc) }|
+            Import { name, path: Item(path.0.iter().cloned().chain(segments).collect(), loc) }
+        ).collect(),
+        (None, Some(name)) => vec![Import { name, path }],
+        (None, None) => vec![Import { name: path.0.last().unwrap().clone(), path }]
+    })).pat());
+    let import = Pattern::named("use", (
+        ExpectIdent("use".to_string()),
+        use_tree,
+        ExpectParticle(';')
+    ), |(_, imports, _), _| imports);
+    let (module_content, module_content_finalizer) = Latent::new();
+    let module = Pattern::named("module", (
+        ExpectIdent("mod".to_string()),
+        GetIdent,
+        Match(vec![
+            (Succeed(ExpectParticle(';').pat()).pat(), ExpectParticle(';').map(|_, _| None).pat()),
+            (Succeed(ExpectParticle('{').pat()).pat(), (ExpectParticle('{'), module_content.clone(), ExpectParticle('}'))
+                .map(|(_, content, _), _| Some(content)).pat())
+        ])
+    ), |(_, name, content), _| match content {
+        Some((items, loc)) => ModDecl::Inline(name, items, loc),
+        None => ModDecl::External(name)
+    });
+    let module_item = (item_tags,
+        Match(vec![
             (Succeed(ExpectIdent("fn".to_string()).pat()).pat(), function.clone().map(|f, _| ModuleContent::Function(f)).pat()),
-            (Succeed(ExpectIdent("const".to_string()).pat()).pat(), constant.clone().map(|c, _| ModuleContent::Const(c)).pat())
-        ])).pat()
-        ).map_res(|content, _| {
+            (Succeed(ExpectIdent("const".to_string()).pat()).pat(), constant.clone().map(|c, _| ModuleContent::Const(c)).pat()),
+            (Succeed(ExpectIdent("mod".to_string()).pat()).pat(), module.clone().map(|m, _| ModuleContent::Module(m)).pat()),
+            (Succeed(ExpectIdent("use".to_string()).pat()).pat(), import.clone().map(|i, _| ModuleContent::Use(i)).pat())
+        ])).pat();
+    module_content_finalizer.finalize(Pattern::named("module content", (
+        // `//!` doc comments document the module they are written in
+        While(GetDoc(true).pat(), Pattern::inline(GetDoc(true), |line, loc| Tag::doc(vec![line], loc))),
+        While(
+        Both(GetNext.pat(), Fail(ExpectParticle('}').pat()).pat()).pat(),
+        // an item that fails to parse is skipped up to the start of the next one
+        Recover(module_item.clone(), SkipUntil(|tt| match tt {
+            TokenType::Ident(i) => matches!(i.as_str(), "fn" | "const" | "mod" | "use"),
+            TokenType::Particle('#', _) | TokenType::Doc(_, false) => true,
+            _ => false
+        }, false).pat()).pat()
+        )).map_res(|(docs, content), _| {
+            let mut module_tags = HashMap::new();
+            docs.into_iter().for_each(|doc| doc.insert_into(&mut module_tags));
             let mut functions = HashMap::new();
-            let mut constants = HashMap::new();
-            for (tags, c) in content.into_iter() {
+            let mut constants: HashMap<String, Const> = HashMap::new();
+            let mut imports: HashMap<String, Import> = HashMap::new();
+            let mut modules: Vec<(HashMap<String, Tag>, ModDecl)> = vec![];
+            for (tags, c) in content.into_iter().flatten() {
                 match c {
                     ModuleContent::Function(mut f) => {
                         f.tags = tags;
                         let l = f.name.1.clone();
-                        if constants.contains_key(&f.name.0){
-                            return Err(LithiaET::AlreadyDefinedError("constant".to_string(), f.name.0).ats(vec![l, f.name.1]))
+                        if let Some(c) = constants.get(&f.name.0){
+                            return Err(LithiaET::AlreadyDefinedError("constant".to_string(), f.name.0)
+                                .at_label(l, "redefined here").at_label(c.name.1.clone(), "first defined here"))
                         }
                         if let Some(f) = functions.insert(f.name.0.clone(), f){
-                            return Err(LithiaET::AlreadyDefinedError("function".to_string(), f.name.0).ats(vec![l, f.name.1]))
+                            return Err(LithiaET::AlreadyDefinedError("function".to_string(), f.name.0)
+                                .at_label(l, "redefined here").at_label(f.name.1, "first defined here"))
                         }
                     },
-                    ModuleContent::Const(c) => {
-                        if tags.len() > 0 {
+                    ModuleContent::Const(mut c) => {
+                        if tags.keys().any(|t| t != "doc") {
                             return Err(LithiaET::TagError("tags not applicable for consts".to_string()).at(c.name.1.clone()))
                         }
+                        c.tags = tags;
                         let l = c.name.1.clone();
-                        if functions.contains_key(&c.name.0){
-                            return Err(LithiaET::AlreadyDefinedError("function".to_string(), c.name.0).ats(vec![l, c.name.1]))
+                        if let Some(f) = functions.get(&c.name.0){
+                            return Err(LithiaET::AlreadyDefinedError("function".to_string(), c.name.0)
+                                .at_label(l, "redefined here").at_label(f.name.1.clone(), "first defined here"))
                         }
                         if let Some(c) = constants.insert(c.name.0.clone(), c){
-                            return Err(LithiaET::AlreadyDefinedError("constant".to_string(), c.name.0).ats(vec![l, c.name.1]))
+                            return Err(LithiaET::AlreadyDefinedError("constant".to_string(), c.name.0)
+                                .at_label(l, "redefined here").at_label(c.name.1, "first defined here"))
+                        }
+                    }
+                    ModuleContent::Module(m) => {
+                        let name = match &m {
+                            ModDecl::Inline(name, _, _) | ModDecl::External(name) => name.clone()
+                        };
+                        if tags.keys().any(|t| t != "doc") {
+                            return Err(LithiaET::TagError("tags not applicable for modules".to_string()).at(name.1.clone()))
+                        }
+                        if let Some((_, ModDecl::Inline(other, _, _) | ModDecl::External(other))) = modules.iter().find(|(_, m)| match m {
+                            ModDecl::Inline(n, _, _) | ModDecl::External(n) => n.0 == name.0
+                        }) {
+                            return Err(LithiaET::AlreadyDefinedError("module".to_string(), name.0)
+                                .at_label(name.1, "redefined here").at_label(other.1.clone(), "first defined here"))
+                        }
+                        modules.push((tags, m))
+                    }
+                    ModuleContent::Use(uses) => {
+                        for u in uses {
+                            if tags.len() > 0 {
+                                return Err(LithiaET::TagError("tags not applicable for imports".to_string()).at(u.name.1.clone()))
+                            }
+                            if let Some(Import { name, .. }) = imports.insert(u.name.0.clone(), u.clone()) {
+                                return Err(LithiaET::AlreadyDefinedError("import".to_string(), u.name.0)
+                                    .at_label(u.name.1, "redefined here").at_label(name.1, "first defined here"))
+                            }
                         }
                     }
                 };
             }
-            Ok((functions, constants))
-        }), |content, loc| (content, loc));
+            // imports share the namespace of functions and constants
+            for (name, import) in &imports {
+                if let Some(other) = functions.get(name).map(|f| &f.name).or_else(|| constants.get(name).map(|c| &c.name)) {
+                    return Err(LithiaET::AlreadyDefinedError("item".to_string(), name.clone())
+                        .at_label(import.name.1.clone(), "imported here").at_label(other.1.clone(), "defined here"))
+                }
+            }
+            Ok((module_tags, functions, constants, imports, modules))
+        }), |content, loc| (content, loc)));
     Patterns {
-        module_content
+        module_content,
+        module_item: module_item.map(|_, _| ()).pat(),
+        expression
     }
 }
@@ -27,6 +27,25 @@ impl Item{
 
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct Tag(pub(crate) Ident, pub(crate) Vec<TagValue>, pub(crate) Span);
+impl Tag {
+    /// A `doc` tag with a string for each line of a doc comment.
+    pub(crate) fn doc(lines: Vec<String>, loc: Span) -> Self {
+        Self(Ident("doc".to_string(), loc.clone()), lines.into_iter().map(|l| TagValue::Lit(AstLiteral(Literal::String(l), loc.clone()))).collect(), loc)
+    }
+
+    /// Adds the tag to `tags`, the lines of a `doc` tag are appended to the existing one.
+    pub(crate) fn insert_into(self, tags: &mut HashMap<String, Tag>) {
+        match tags.get_mut(&self.0.0) {
+            Some(Tag(Ident(name, _), lines, loc)) if name == "doc" => {
+                lines.extend(self.1);
+                loc.combine(self.2);
+            }
+            _ => {
+                tags.insert(self.0.0.clone(), self);
+            }
+        }
+    }
+}
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum TagValue {
     Lit(AstLiteral),
@@ -49,7 +68,7 @@ pub(crate) enum Expr {
     Block(Block),
     Expr(Box<Expression>),
     If(Box<Expression>, Block, Block),
-    Loop()
+    Loop(),
     FuncCall(Item, Vec<Expression>),
     BinaryOp(Operator, Box<Expression>, Box<Expression>),
     UnaryOp(Operator, Box<Expression>),
@@ -75,6 +94,7 @@ impl Expr {
             Expr::VarCreate(_, _, _, _) => false,
             Expr::VarAssign(_, _, _) => false,
             Expr::Return(_) => false,
+            Expr::Loop() => true,
         }
     }
 }
@@ -107,10 +127,13 @@ pub(crate) struct Statement(pub(crate) Expression, pub(crate) bool, pub(crate) S
 
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct Module{
+    /// only `doc`, from the doc comments before `mod` and the `//!` ones inside the module
+    pub(crate) tags: HashMap<String, Tag>,
     pub(crate) name: Ident,
     pub(crate) sub_modules: HashMap<String, Module>,
     pub(crate) functions: HashMap<String, Func>,
     pub(crate) constants: HashMap<String, Const>,
+    pub(crate) imports: HashMap<String, Import>,
     pub(crate) loc: Span
 }
 
@@ -127,8 +150,17 @@ pub(crate) struct Func {
     pub(crate) loc: Span
 }
 
+/// `use path as name;`, makes the item or module at `path` available as `name`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Import {
+    pub(crate) name: Ident,
+    pub(crate) path: Item
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct Const {
+    /// only `doc`
+    pub(crate) tags: HashMap<String, Tag>,
     pub(crate) name: Ident,
     pub(crate) ty: Type,
     pub(crate) val: Expression
@@ -158,7 +190,8 @@ impl AstLiteral {
     pub(crate) fn get_type(&self) -> Result<Type, LithiaError>{
         Ok(match &self.0 {
             Literal::String(s) => Type(Ty::Array(Box::new(Type(Ty::Single(vec![], Item::new(&vec!["u8"], self.1.clone())), self.1.clone())), s.len() + 1), self.1.clone()),
-            Literal::Char(_) => Type(Ty::Single(vec![], Item::new(&vec!["u8"], self.1.clone())), self.1.clone()),
+            // a char that does not fit a byte is its code point
+            Literal::Char(c) => Type(Ty::Single(vec![], Item::new(&vec![if c.is_ascii() { "u8" } else { "u32" }], self.1.clone())), self.1.clone()),
             Literal::Number(_,  Some(ty)) => Type(Ty::Single(vec![], Item::new(&vec![&format!("{ty}")], self.1.clone())), self.1.clone()),
             Literal::Number(_,  None) => unimplemented!(),
             Literal::Bool(_) => Type(Ty::Single(vec![], Item::new(&vec!["bool"], self.1.clone())), self.1.clone()),
@@ -1,18 +1,163 @@
-use crate::ast::{Ident, Module};
-use crate::ast::create_patterns::build_patterns;
-use crate::error::LithiaError;
+use std::collections::HashMap;
+use std::mem;
+use std::path::{Path, PathBuf};
+use crate::ast::{Expression, Ident, Module};
+use crate::ast::create_patterns::{build_patterns, ModDecl, ModuleItems};
+use crate::ast::patterns::Consumer;
+use crate::ast::patterns::simple::ExpectParticle;
+use crate::diagnostics::Diagnostics;
+use crate::error::{LithiaET, OnParseErr};
+use crate::source::Source;
 use crate::source::span::Span;
-use crate::tokens::{Token, TokIter};
+use crate::tokens::{Token, TokenType, TokIter};
+use crate::tokens::tokenizer::tokenize;
 
-pub(crate) fn parse(tokens: Vec<Token>, mod_name: (String, Option<Span>)) -> Result<Module, LithiaError>{
+pub(crate) fn parse(tokens: Vec<Token>, mod_name: (String, Option<Span>)) -> Result<Module, Diagnostics>{
+    // `mod name;` declarations of the root module are looked up next to its file
+    let dir = tokens.first()
+        .and_then(|t| t.loc.source.path().and_then(|p| p.parent()).map(Path::to_path_buf))
+        .unwrap_or_default();
+    parse_module(tokens, mod_name, &dir)
+}
+
+/// Parses the module and the files of its sub modules, errors of items that could be skipped are collected.
+fn parse_module(tokens: Vec<Token>, mod_name: (String, Option<Span>), dir: &Path) -> Result<Module, Diagnostics>{
     let patterns = build_patterns();
-    let mut tokens = TokIter::new(tokens);
-    let ((functions, constants), loc) = patterns.module_content.consume(&mut tokens)?;
-    Ok(Module{
-        name: Ident(mod_name.0, mod_name.1.unwrap_or(loc.clone())),
-        sub_modules: Default::default(),
+    let mut tokens = TokIter::new(drop_stray_docs(tokens));
+    let content = patterns.module_content.consume(&mut tokens);
+    let mut diagnostics = mem::take(&mut tokens.diagnostics);
+    let (items, loc) = match content {
+        Ok(content) => content,
+        Err(e) => {
+            diagnostics.push(e);
+            return Err(diagnostics)
+        }
+    };
+    if tokens.elems_left() > 0 {
+        if let Err(e) = patterns.module_item.consume(&mut tokens) {
+            diagnostics.push(e);
+        }
+    }
+    let module = build_module(Ident(mod_name.0, mod_name.1.unwrap_or(loc.clone())), items, loc, dir, &mut diagnostics);
+    diagnostics.check()?;
+    Ok(module)
+}
+
+/// Builds the module and all of its sub modules, `dir` is where the files of `mod name;` declarations are searched.
+fn build_module(name: Ident, (tags, functions, constants, imports, decls): ModuleItems, loc: Span, dir: &Path, diagnostics: &mut Diagnostics) -> Module{
+    let mut sub_modules = HashMap::new();
+    for (doc, decl) in decls {
+        let mut module = match decl {
+            ModDecl::Inline(name, items, loc) => {
+                let dir = dir.join(&name.0);
+                build_module(name, items, loc, &dir, diagnostics)
+            }
+            ModDecl::External(name) => match load_module(name, dir) {
+                Ok(module) => module,
+                Err(errors) => {
+                    diagnostics.append(errors);
+                    continue
+                }
+            }
+        };
+        // the doc comments before `mod` come first, then the `//!` ones of the module
+        let inner = mem::replace(&mut module.tags, doc);
+        inner.into_values().for_each(|tag| tag.insert_into(&mut module.tags));
+        sub_modules.insert(module.name.0.clone(), module);
+    }
+    Module{
+        tags,
+        name,
+        sub_modules,
         functions,
         constants,
+        imports,
         loc
-    })
-}
\ No newline at end of file
+    }
+}
+
+/// Loads `mod name;` from either `dir/name.li` or `dir/name/mod.li`.
+fn load_module(name: Ident, dir: &Path) -> Result<Module, Diagnostics>{
+    let candidates: [PathBuf; 2] = [dir.join(format!("{}.li", name.0)), dir.join(&name.0).join("mod.li")];
+    let Some(path) = candidates.iter().find(|p| p.is_file()) else {
+        return Err(LithiaET::ParsingError(format!("file not found for module {}, expected {} or {}", name.0,
+                                                  candidates[0].display(), candidates[1].display())).at(name.1).into())
+    };
+    let source = Source::from_file(path.to_string_lossy().to_string()).e_when(format!("loading module {}", name.0))?;
+    let dir = dir.join(&name.0);
+    parse_module(tokenize(source)?, (name.0, Some(name.1)), &dir)
+}
+
+/// Parses a single expression, optionally terminated by `;`, which has to span all tokens.
+pub(crate) fn parse_expression(tokens: Vec<Token>) -> Result<Expression, Diagnostics>{
+    let patterns = build_patterns();
+    let mut tokens = TokIter::new(tokens.into_iter().filter(|t| !matches!(t.tt, TokenType::Doc(..))).collect());
+    let expr = patterns.expression.consume(&mut tokens);
+    let mut diagnostics = mem::take(&mut tokens.diagnostics);
+    let expr = match expr {
+        Ok(expr) => expr,
+        Err(e) => {
+            diagnostics.push(e);
+            return Err(diagnostics)
+        }
+    };
+    if tokens.elems_left() > 0 {
+        let _ = ExpectParticle(';').consume(&mut tokens);
+    }
+    if tokens.elems_left() > 0 {
+        let unexpected = tokens.this().map(|t| format!("unexpected {:?} after expression", t.tt)).unwrap_or_default();
+        diagnostics.push(LithiaET::ParsingError(unexpected).at(tokens.here()));
+    }
+    diagnostics.check()?;
+    Ok(expr)
+}
+
+/// The keyword of the item the tokens start with, after its tags and doc comments.
+pub(crate) fn item_keyword(tokens: &[Token]) -> Option<&str> {
+    let mut i = 0;
+    loop {
+        match tokens.get(i).map(|t| &t.tt) {
+            Some(TokenType::Doc(..)) => {
+                i += 1;
+                continue
+            }
+            Some(TokenType::Particle('#', _)) => (),
+            _ => break
+        }
+        let mut depth = 0;
+        i += 1;
+        while let Some(t) = tokens.get(i) {
+            i += 1;
+            match t.tt {
+                TokenType::Particle('[', _) => depth += 1,
+                TokenType::Particle(']', _) => {
+                    depth -= 1;
+                    if depth == 0 { break }
+                }
+                _ => ()
+            }
+        }
+    }
+    match tokens.get(i).map(|t| &t.tt) {
+        Some(TokenType::Ident(ident)) => Some(ident.as_str()),
+        _ => None
+    }
+}
+
+/// Doc comments are ordinary comments unless `///` is followed by a function, constant or module,
+/// or `//!` starts a file or the content of `mod name { ... }`, so the others are dropped.
+fn drop_stray_docs(tokens: Vec<Token>) -> Vec<Token> {
+    let mut kept: Vec<Token> = vec![];
+    let mut module_start = true;
+    for (i, token) in tokens.iter().enumerate() {
+        match &token.tt {
+            TokenType::Doc(_, true) if !module_start => continue,
+            TokenType::Doc(_, false) if !matches!(item_keyword(&tokens[i..]), Some("fn" | "const" | "mod")) => continue,
+            TokenType::Doc(_, true) => (),
+            tt => module_start = matches!(tt, TokenType::Particle('{', _)) && matches!(kept.as_slice(),
+                [.., Token { tt: TokenType::Ident(keyword), .. }, Token { tt: TokenType::Ident(_), .. }] if keyword == "mod")
+        }
+        kept.push(token.clone());
+    }
+    kept
+}
@@ -1,35 +1,42 @@
 use crate::ast::patterns::{Consumer, Pat};
 use crate::error::{LithiaError, LithiaET};
 use crate::tokens::TokIter;
+use crate::util::indexer::Expected;
 
 pub(crate) struct While<Pred, Item>(pub(crate) Pat<Pred>, pub(crate) Pat<Item>);
 impl<Pred, Item> Consumer for While<Pred, Item> {
     type Output = Vec<Item>;
 
     fn consume(&self, iter: &mut TokIter) -> Result<Self::Output, LithiaError> {
-        let mut pred_it = iter.clone();
         let mut out = vec![];
-        while self.0.consume(&mut pred_it).is_ok() {
+        loop {
+            let mut pred_it = iter.clone();
+            let more = self.0.consume(&mut pred_it).is_ok();
+            // a predicate like `Fail(ExpectParticle(')'))` that passes still expected the `)`
+            iter.merge_expected(pred_it);
+            if !more {
+                return Ok(out)
+            }
             out.push(self.1.consume(iter)?);
-            pred_it = iter.clone();
         }
-        Ok(out)
     }
 }
 
+/// Consumes the item of the first branch whose predicate passes. If none does, the error lists what all
+/// predicates expected at the furthest token they reached.
 pub(crate) struct Match<Item>(pub(crate) Vec<(Pat<()>, Pat<Item>)>);
 impl<Item> Consumer for Match<Item> {
     type Output = Item;
 
     fn consume(&self, iter: &mut TokIter) -> Result<Self::Output, LithiaError> {
-        let start = iter.here();
         for (pred, item) in &self.0 {
             let mut pred_it = iter.clone();
             if pred.consume(&mut pred_it).is_ok() {
                 return item.consume(iter)
             }
+            iter.merge_expected(pred_it);
         }
-        Err(LithiaET::ParsingError(format!("could not match to any branch in match, found {:?}", iter.this()?.tt)).at(start))
+        Err(iter.expected_error())
     }
 }
 
@@ -38,9 +45,14 @@ impl<Pred, Out> Consumer for Optional<Pred, Out>{
     type Output = Option<Out>;
 
     fn consume(&self, iter: &mut TokIter) -> Result<Self::Output, LithiaError> {
-        Ok(if self.0.consume(&mut iter.clone()).is_ok() {
+        let mut pred_it = iter.clone();
+        Ok(if self.0.consume(&mut pred_it).is_ok() {
             Some(self.1.consume(iter)?)
-        } else { None })
+        } else {
+            // whatever fails next also reports what the predicate expected
+            iter.merge_expected(pred_it);
+            None
+        })
     }
 }
 
@@ -49,9 +61,13 @@ impl<Pred, Out> Consumer for Or<Pred, Out>{
     type Output = Out;
 
     fn consume(&self, iter: &mut TokIter) -> Result<Self::Output, LithiaError> {
-        Ok(if self.0.consume(&mut iter.clone()).is_ok() {
+        let mut pred_it = iter.clone();
+        Ok(if self.0.consume(&mut pred_it).is_ok() {
             self.1.consume(iter)?
-        } else { self.2.consume(iter)? })
+        } else {
+            iter.merge_expected(pred_it);
+            self.2.consume(iter)?
+        })
     }
 }
 
@@ -109,4 +125,29 @@ impl<Out> Consumer for IsOk<Out>{
     fn consume(&self, iter: &mut TokIter) -> Result<Self::Output, LithiaError> {
         Ok(self.0.consume(iter).is_ok())
     }
-}
\ No newline at end of file
+}
+/// Records the error of a failing pattern in the diagnostics of the iterator and skips ahead with the second pattern
+/// from where the failing one stopped, so parsing can continue after it. Fails at the end of the file.
+pub(crate) struct Recover<Out>(pub(crate) Pat<Out>, pub(crate) Pat<()>);
+impl<Out> Consumer for Recover<Out>{
+    type Output = Option<Out>;
+
+    fn consume(&self, iter: &mut TokIter) -> Result<Self::Output, LithiaError> {
+        let start = iter.index;
+        match self.0.consume(iter) {
+            Ok(out) => Ok(Some(out)),
+            Err(e) => {
+                if iter.index == start {
+                    if iter.elems_left() == 0 {
+                        return Err(e)
+                    }
+                    iter.next();
+                }
+                iter.diagnostics.push(e);
+                iter.expected = Expected::default();
+                self.1.consume(iter)?;
+                Ok(None)
+            }
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use std::rc::Rc;
-use crate::error::{LithiaError};
+use crate::error::{LithiaError, LithiaET};
 use crate::source::span::Span;
 use crate::tokens::TokIter;
+use crate::util::indexer::Expected;
 
 pub(crate) mod simple;
 pub(crate) mod conditional;
@@ -42,7 +43,9 @@ impl<T: Consumer, Out> Consumer for Pattern<T, Out> {
         if out.is_err() && self.name.is_some() {
             return Err(unsafe {out.unwrap_err_unchecked()}.when(format!("parsing {}", self.name.clone().unwrap())));
         }
-        start.combine(iter.peekn(-1)?.loc);
+        if iter.index > 0 {
+            start.combine(iter.peekn(-1)?.loc);
+        }
         Ok((self.mapper)(out?, start))
     }
 }
@@ -61,6 +64,38 @@ impl<Out, T: Consumer<Output=Out>> Consumer for Rc<T> {
     }
 }
 
+impl TokIter {
+    /// Records that `what` was expected at the current token and returns the error for everything
+    /// expected at the furthest token reached, which includes the alternatives tried by lookaheads.
+    pub(crate) fn expect<S: Into<String>>(&mut self, what: S) -> LithiaError {
+        self.expected.merge(Expected { index: self.index, items: vec![what.into()] });
+        self.expected_error()
+    }
+
+    /// The error for everything expected at the furthest token reached.
+    pub(crate) fn expected_error(&self) -> LithiaError {
+        let Expected { index, items } = &self.expected;
+        let expected = match items.as_slice() {
+            [] => None,
+            [single] => Some(single.clone()),
+            items => Some(format!("one of {}", items.join(", ")))
+        };
+        match (self.get(*index), expected) {
+            (Ok(token), Some(expected)) => LithiaET::ParsingError(format!("expected {expected} — found {}", token.tt)).at(token.loc),
+            (Ok(token), None) => LithiaET::ParsingError(format!("unexpected {}", token.tt)).at(token.loc),
+            (Err(eof), expected) => {
+                let mut end = self.clone();
+                end.index = *index;
+                let eof = eof.at(end.here());
+                match expected {
+                    Some(expected) => eof.when(format!("expecting {expected}")),
+                    None => eof
+                }
+            }
+        }
+    }
+}
+
 pub(crate) trait Consumer {
     type Output;
     fn consume(&self, iter: &mut TokIter) -> Result<Self::Output, LithiaError>;
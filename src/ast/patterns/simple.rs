@@ -1,7 +1,7 @@
 use crate::ast::{AstLiteral, Ident};
 use crate::ast::patterns::{Consumer, Pat};
-use crate::error::{OnParseErr, LithiaError, LithiaET};
-use crate::tokens::{Token, TokenType, TokIter, Literal, glued};
+use crate::error::{LithiaError, LithiaET};
+use crate::tokens::{Token, TokenType, TokIter, Literal, glued, inner};
 
 pub(crate) struct Wrap<Out>(pub(crate) Pat<Out>);
 
@@ -29,16 +29,12 @@ impl Consumer for ExpectIdent {
     type Output = ();
 
     fn consume(&self, iter: &mut TokIter) -> Result<Self::Output, LithiaError> {
-        let Token { tt, loc } = iter.this()?;
-        if let TokenType::Ident(s) = tt {
-            if s == self.0 {
+        match iter.this() {
+            Ok(Token { tt: TokenType::Ident(s), .. }) if s == self.0 => {
                 iter.next();
                 Ok(())
-            } else {
-                Err(LithiaET::ParsingError(format!("expected '{}', found {}", self.0, s)).at(loc))
             }
-        } else {
-            Err(LithiaET::ParsingError(format!("expected '{}', found {:?}", self.0, tt)).at(loc))
+            _ => Err(iter.expect(format!("`{}`", self.0)))
         }
     }
 }
@@ -47,12 +43,12 @@ impl Consumer for GetIdent {
     type Output = Ident;
 
     fn consume(&self, iter: &mut TokIter) -> Result<Self::Output, LithiaError> {
-        let Token { tt, loc } = iter.this()?;
-        if let TokenType::Ident(s) = tt {
-            iter.next();
-            Ok(Ident(s, loc))
-        } else {
-            Err(LithiaET::ParsingError(format!("expected Ident, found {:?}", tt)).at(loc))
+        match iter.this() {
+            Ok(Token { tt: TokenType::Ident(s), loc }) => {
+                iter.next();
+                Ok(Ident(s, loc))
+            }
+            _ => Err(iter.expect("identifier"))
         }
     }
 }
@@ -62,16 +58,12 @@ impl Consumer for ExpectParticle {
     type Output = ();
 
     fn consume(&self, iter: &mut TokIter) -> Result<Self::Output, LithiaError> {
-        let Token { tt, loc } = iter.this()?;
-        if let TokenType::Particle(c, _) = tt {
-            if c == self.0 {
+        match iter.this() {
+            Ok(Token { tt: TokenType::Particle(c, _), .. }) if c == self.0 => {
                 iter.next();
                 Ok(())
-            } else {
-                Err(LithiaET::ParsingError(format!("expected '{}', found '{}'", self.0, c)).at(loc))
             }
-        } else {
-            Err(LithiaET::ParsingError(format!("expected '{}', found {:?}", self.0, tt)).at(loc))
+            _ => Err(iter.expect(format!("`{}`", self.0)))
         }
     }
 }
@@ -81,17 +73,13 @@ impl Consumer for ExpectParticleExact {
     type Output = ();
 
     fn consume(&self, iter: &mut TokIter) -> Result<Self::Output, LithiaError> {
-        let Token { tt, loc } = iter.this()?;
-        if let TokenType::Particle(c, g) = tt {
-            if c == self.0 && g == self.1 {
+        match iter.this() {
+            Ok(Token { tt: TokenType::Particle(c, g), .. }) if c == self.0 && g == self.1 => {
                 iter.next();
                 Ok(())
-            } else {
-                Err(LithiaET::ParsingError(format!("expected ('{}', {}), found ('{}', {})", self.0, self.1, c, g)).at(loc))
             }
-        } else {
-            Err(LithiaET::ParsingError(format!("expected ('{}', {}), found ({:?})", self.0, self.1, tt)).at(loc))
-        }.e_when("parsing ident")
+            _ => Err(iter.expect(format!("`{}`", self.0)))
+        }
     }
 }
 pub(crate) struct GetParticle;
@@ -99,12 +87,12 @@ impl Consumer for GetParticle {
     type Output = (char, glued);
 
     fn consume(&self, iter: &mut TokIter) -> Result<Self::Output, LithiaError> {
-        let Token { tt, loc } = iter.this()?;
-        if let TokenType::Particle(p, g) = tt {
-            iter.next();
-            Ok((p, g))
-        } else {
-            Err(LithiaET::ParsingError(format!("expected Particle, found {:?}", tt)).at(loc))
+        match iter.this() {
+            Ok(Token { tt: TokenType::Particle(p, g), .. }) => {
+                iter.next();
+                Ok((p, g))
+            }
+            _ => Err(iter.expect("operator"))
         }
     }
 }
@@ -113,12 +101,12 @@ impl Consumer for GetGluedParticle {
     type Output = char;
 
     fn consume(&self, iter: &mut TokIter) -> Result<Self::Output, LithiaError> {
-        let Token { tt, loc } = iter.this()?;
-        if let TokenType::Particle(p, true) = tt {
-            iter.next();
-            Ok(p)
-        } else {
-            Err(LithiaET::ParsingError(format!("expected glued Particle, found {:?}", tt)).at(loc))
+        match iter.this() {
+            Ok(Token { tt: TokenType::Particle(p, true), .. }) => {
+                iter.next();
+                Ok(p)
+            }
+            _ => Err(iter.expect("operator"))
         }
     }
 }
@@ -127,16 +115,12 @@ impl Consumer for ExpectLiteral {
     type Output = ();
 
     fn consume(&self, iter: &mut TokIter) -> Result<Self::Output, LithiaError> {
-        let Token { tt, loc } = iter.this()?;
-        if let TokenType::Literal(lit) = tt {
-            if lit == self.0 {
+        match iter.this() {
+            Ok(Token { tt: TokenType::Literal(lit), .. }) if lit == self.0 => {
                 iter.next();
                 Ok(())
-            } else {
-                Err(LithiaET::ParsingError(format!("expected {:?}, found {:?}", self.0, lit)).at(loc))
             }
-        } else {
-            Err(LithiaET::ParsingError(format!("expected {:?}, found {:?}", self.0, tt)).at(loc))
+            _ => Err(iter.expect(TokenType::Literal(self.0.clone()).to_string()))
         }
     }
 }
@@ -145,13 +129,56 @@ impl Consumer for GetLiteral {
     type Output = AstLiteral;
 
     fn consume(&self, iter: &mut TokIter) -> Result<Self::Output, LithiaError> {
-        let Token { tt, loc } = iter.this()?;
-        if let TokenType::Literal(lit) = tt {
-            iter.next();
-            Ok(AstLiteral(lit, loc))
-        } else {
-            Err(LithiaET::ParsingError(format!("expected Literal, found {:?}", tt)).at(loc))
+        match iter.this() {
+            Ok(Token { tt: TokenType::Literal(lit), loc }) => {
+                iter.next();
+                Ok(AstLiteral(lit, loc))
+            }
+            _ => Err(iter.expect("literal"))
         }
     }
 }
 
+/// A line of a doc comment, `//!` ones if the given `inner` is true.
+pub(crate) struct GetDoc(pub(crate) inner);
+impl Consumer for GetDoc {
+    type Output = String;
+
+    fn consume(&self, iter: &mut TokIter) -> Result<Self::Output, LithiaError> {
+        match iter.this() {
+            Ok(Token { tt: TokenType::Doc(doc, inner), .. }) if inner == self.0 => {
+                iter.next();
+                Ok(doc)
+            }
+            // a missing doc comment is never the reason for an error, so it is not listed as expected
+            _ => Err(LithiaET::ParsingError("expected doc comment".to_string()).at(iter.here()))
+        }
+    }
+}
+
+/// Skips tokens until `stop` accepts one outside of any `{}` opened while skipping, which is skipped as well if `inclusive`.
+/// A `}` closing the surrounding block is never skipped.
+pub(crate) struct SkipUntil(pub(crate) fn(&TokenType) -> bool, pub(crate) bool);
+impl Consumer for SkipUntil {
+    type Output = ();
+
+    fn consume(&self, iter: &mut TokIter) -> Result<Self::Output, LithiaError> {
+        let mut depth = 0usize;
+        while let Ok(Token { tt, .. }) = iter.this() {
+            match tt {
+                TokenType::Particle('{', _) => depth += 1,
+                TokenType::Particle('}', _) if depth == 0 => break,
+                TokenType::Particle('}', _) => depth -= 1,
+                _ if depth == 0 && (self.0)(&tt) => {
+                    if self.1 {
+                        iter.next();
+                    }
+                    break
+                }
+                _ => ()
+            }
+            iter.next();
+        }
+        Ok(())
+    }
+}
@@ -75,14 +75,18 @@ impl Type {
         if s == sat {
             Ok(())
         } else {
-            Err(LithiaET::TypeError(other.clone(), self.clone()).ats(vec![self.1.clone(), other.1.clone()]))
+            Err(LithiaET::TypeError(other.clone(), self.clone())
+                .at_label(self.1.clone(), format!("found {} here", self.print()))
+                .at_label(other.1.clone(), format!("expected {} here", other.print())))
         }
     }
     pub(crate) fn equals_or_err(&self, other: &Type) -> Result<(), LithiaError> {
         if self == other {
             Ok(())
         } else {
-            Err(LithiaET::TypeError(other.clone(), self.clone()).ats(vec![self.1.clone(), other.1.clone()]))
+            Err(LithiaET::TypeError(other.clone(), self.clone())
+                .at_label(self.1.clone(), format!("found {} here", self.print()))
+                .at_label(other.1.clone(), format!("expected {} here", other.print())))
         }
     }
 }
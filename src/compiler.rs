@@ -1,26 +1,455 @@
+use std::{env, fs};
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use llvm_sys::core;
+use llvm_sys::prelude::LLVMModuleRef;
+use llvm_sys::target_machine::LLVMCodeGenFileType;
 use crate::ast::code_printer::CodePrinter;
 use crate::ast::parser::parse;
-use crate::error::LithiaError;
-use crate::llvm::gen_llvm::{build_exe, build_llvm_ir};
+use crate::diagnostics::Diagnostics;
+use crate::error::{LithiaError, LithiaET, OnParseErr};
+use crate::error_codes::explain;
+use crate::lints::{LintLevel, LintLevels};
+use crate::llvm::gen_llvm::{archive_static_lib, build_llvm_ir, build_test_harness, link_exe, link_shared_lib, write_bitcode, write_llvm_ir};
+use crate::llvm::jit::Jit;
+use crate::llvm::target::{OptLevel, Target};
+use crate::repl::repl;
 use crate::source::Source;
+use crate::test_runner::{collect_tests, run_tests};
 use crate::tokens::tokenizer::tokenize;
 
+pub(crate) const USAGE: &str = "usage: lithia <build|run|check|test> <input.li> [-o <output>] [--emit=<kinds>] [--crate-type=<type>] [--target <triple>] [-O<level>] [-g] [--jit] [-v...] [--error-format=<format>] [-A|-W|-D <lint>] [-- <program args>...]
+       lithia repl [<input.li>]
+       lithia --explain <code>
+
+subcommands:
+    build    compile the input file to an executable
+    run      compile the input file and run the resulting executable
+    check    only tokenize and parse the input file
+    test     build the functions tagged with #[test] into a harness and run each of them
+    repl     evaluate items and expressions interactively, after loading the items of the input file
+    --explain
+             print a detailed explanation of an error code, e.g. L0014
+
+options:
+    -o, --output <path>    path of the produced executable, other artifacts are written next to it
+    --emit=<kinds>         comma separated list of artifacts to write:
+                           tokens, ast, llvm-ir, bc, asm, obj, exe, staticlib, cdylib (default: the crate type's artifact)
+    --crate-type=<type>    with build: what to produce, libraries have no implicit main and export their functions
+                           bin (executable), staticlib (.a archive), cdylib (shared library), obj (object file)
+                           (default: bin)
+    --target <triple>      with build: compile for another target, e.g. aarch64-unknown-linux-gnu (default: host)
+                           executables are linked with $CC, which has to be a linker for that target
+    -O0, -O1, -O2, -O3, -Os
+                           with build, run and test: optimization level, runs the matching default LLVM pass pipeline (default: -O0)
+    -g                     with build, run and test: emit DWARF debug info
+    --jit                  with run: execute main in-process instead of building an executable
+    -v, --verbose          print more information, may be repeated
+    --error-format=<format>
+                           how errors are printed: human, or json with one object per error and line (default: human)
+    -A, -W, -D <lint>      allow, warn about or deny a lint, e.g. -D warnings, later flags override earlier ones
+                           lints: unused_variables, unused_results, unused_unsafe, unreachable_code, shadowed_bindings
+                           groups: unused (all unused_* lints), warnings (all lints that warn by default)
+    -- <program args>...   with run: arguments passed on to the program";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Subcommand {
+    Build,
+    Run,
+    Check,
+    Test,
+    Repl,
+    /// `--explain <code>`, the code is passed as input
+    Explain
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Emit {
+    Tokens,
+    Ast,
+    LlvmIr,
+    Bitcode,
+    Asm,
+    Object,
+    Exe,
+    StaticLib,
+    SharedLib
+}
+
+/// What a build produces, only executables get a C `main` calling the `main` of the module.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum CrateType {
+    Bin,
+    StaticLib,
+    SharedLib,
+    Object
+}
+
+impl CrateType {
+    pub(crate) fn from_name(name: &str) -> Result<Self, LithiaError> {
+        Ok(match name {
+            "bin" => CrateType::Bin,
+            "staticlib" => CrateType::StaticLib,
+            "cdylib" => CrateType::SharedLib,
+            "obj" => CrateType::Object,
+            t => return Err(LithiaET::ArgumentError(format!("unknown crate type '{t}', expected one of bin, staticlib, cdylib, obj")).error())
+        })
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            CrateType::Bin => "bin",
+            CrateType::StaticLib => "staticlib",
+            CrateType::SharedLib => "cdylib",
+            CrateType::Object => "obj"
+        }
+    }
+
+    /// The artifact written for the crate type.
+    pub(crate) fn artifact(&self) -> Emit {
+        match self {
+            CrateType::Bin => Emit::Exe,
+            CrateType::StaticLib => Emit::StaticLib,
+            CrateType::SharedLib => Emit::SharedLib,
+            CrateType::Object => Emit::Object
+        }
+    }
+
+    pub(crate) fn has_entrypoint(&self) -> bool {
+        *self == CrateType::Bin
+    }
+}
+
+/// How errors are printed, `Json` writes one object per error and line for editors and CI.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ErrorFormat {
+    Human,
+    Json
+}
+
+impl ErrorFormat {
+    pub(crate) fn from_name(name: &str) -> Result<Self, LithiaError> {
+        Ok(match name {
+            "human" => ErrorFormat::Human,
+            "json" => ErrorFormat::Json,
+            f => return Err(LithiaET::ArgumentError(format!("unknown error format '{f}', expected one of human, json")).error())
+        })
+    }
+}
+
+impl Emit {
+    pub(crate) fn from_name(name: &str) -> Result<Self, LithiaError> {
+        Ok(match name {
+            "tokens" => Emit::Tokens,
+            "ast" => Emit::Ast,
+            "llvm-ir" => Emit::LlvmIr,
+            "bc" => Emit::Bitcode,
+            "asm" => Emit::Asm,
+            "obj" => Emit::Object,
+            "exe" => Emit::Exe,
+            "staticlib" => Emit::StaticLib,
+            "cdylib" => Emit::SharedLib,
+            e => return Err(LithiaET::ArgumentError(format!("unknown emit kind '{e}'")).error())
+        })
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Emit::Tokens => "tokens",
+            Emit::Ast => "ast",
+            Emit::LlvmIr => "llvm-ir",
+            Emit::Bitcode => "bc",
+            Emit::Asm => "asm",
+            Emit::Object => "obj",
+            Emit::Exe => "exe",
+            Emit::StaticLib => "staticlib",
+            Emit::SharedLib => "cdylib"
+        }
+    }
+
+    pub(crate) fn extension(&self) -> &'static str {
+        match self {
+            Emit::Tokens => "tokens",
+            Emit::Ast => "ast",
+            Emit::LlvmIr => "ll",
+            Emit::Bitcode => "bc",
+            Emit::Asm => "s",
+            Emit::Object => "o",
+            Emit::Exe => "",
+            Emit::StaticLib => "a",
+            Emit::SharedLib => env::consts::DLL_EXTENSION
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub(crate) struct Arguments{
+    pub(crate) command: Subcommand,
+    pub(crate) input: PathBuf,
+    pub(crate) output: Option<PathBuf>,
+    pub(crate) emit: Vec<Emit>,
+    pub(crate) crate_type: CrateType,
+    pub(crate) target: Option<String>,
+    pub(crate) opt_level: OptLevel,
+    pub(crate) debug_info: bool,
+    pub(crate) jit: bool,
+    pub(crate) verbosity: u8,
+    pub(crate) error_format: ErrorFormat,
+    pub(crate) lint_levels: LintLevels,
+    /// passed to the main of the program by run
+    pub(crate) program_args: Vec<String>
+}
+
+impl Arguments {
+    /// Parses the command line, expects the program name to already be skipped.
+    pub(crate) fn from_args<I: Iterator<Item=String>>(mut args: I) -> Result<Self, LithiaError> {
+        let command = match args.next().as_deref() {
+            Some("build") => Subcommand::Build,
+            Some("run") => Subcommand::Run,
+            Some("check") => Subcommand::Check,
+            Some("test") => Subcommand::Test,
+            Some("repl") => Subcommand::Repl,
+            Some("--explain") => Subcommand::Explain,
+            Some(c) => return Err(LithiaET::ArgumentError(format!("unknown subcommand '{c}'")).error()),
+            None => return Err(LithiaET::ArgumentError("expected subcommand".to_string()).error())
+        };
+        let mut input = None;
+        let mut output = None;
+        let mut emit = vec![];
+        let mut crate_type = None;
+        let mut target = None;
+        let mut opt_level = None;
+        let mut debug_info = false;
+        let mut jit = false;
+        let mut verbosity = 0;
+        let mut error_format = ErrorFormat::Human;
+        let mut lint_levels = LintLevels::default();
+        let mut program_args = vec![];
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--" => program_args = args.by_ref().collect(),
+                "--emit" => emit.append(&mut Self::parse_emit(&args.next()
+                    .ok_or_else(|| LithiaET::ArgumentError("expected emit kinds after --emit".to_string()).error())?)?),
+                kinds if kinds.starts_with("--emit=") => emit.append(&mut Self::parse_emit(&kinds["--emit=".len()..])?),
+                "-o" | "--output" => output = Some(PathBuf::from(args.next()
+                    .ok_or_else(|| LithiaET::ArgumentError(format!("expected path after {arg}")).error())?)),
+                "--crate-type" => crate_type = Some(CrateType::from_name(&args.next()
+                    .ok_or_else(|| LithiaET::ArgumentError("expected crate type after --crate-type".to_string()).error())?)?),
+                name if name.starts_with("--crate-type=") => crate_type = Some(CrateType::from_name(&name["--crate-type=".len()..])?),
+                "--target" => target = Some(args.next()
+                    .ok_or_else(|| LithiaET::ArgumentError("expected triple after --target".to_string()).error())?),
+                triple if triple.starts_with("--target=") => target = Some(triple["--target=".len()..].to_string()),
+                "--error-format" => error_format = ErrorFormat::from_name(&args.next()
+                    .ok_or_else(|| LithiaET::ArgumentError("expected format after --error-format".to_string()).error())?)?,
+                format if format.starts_with("--error-format=") => error_format = ErrorFormat::from_name(&format["--error-format=".len()..])?,
+                "-A" | "-W" | "-D" => lint_levels.set(LintLevel::from_flag(&arg).unwrap(), &arg, &args.next()
+                    .ok_or_else(|| LithiaET::ArgumentError(format!("expected lint name after {arg}")).error())?)?,
+                lint if lint.get(..2).and_then(LintLevel::from_flag).is_some() => lint_levels.set(LintLevel::from_flag(&lint[..2]).unwrap(), &lint[..2], &lint[2..])?,
+                "-g" => debug_info = true,
+                "--jit" => jit = true,
+                level if level.starts_with("-O") => opt_level = Some(OptLevel::from_flag(level)
+                    .ok_or_else(|| LithiaET::ArgumentError(format!("unknown optimization level '{level}', expected one of -O0, -O1, -O2, -O3, -Os")).error())?),
+                "-v" | "--verbose" => verbosity += 1,
+                flag if flag.starts_with('-') => return Err(LithiaET::ArgumentError(format!("unknown option '{flag}'")).error()),
+                file => {
+                    if input.is_some() {
+                        return Err(LithiaET::ArgumentError(format!("unexpected argument '{file}', input file was already given")).error())
+                    }
+                    input = Some(PathBuf::from(file))
+                }
+            }
+        }
+        if jit && command != Subcommand::Run {
+            return Err(LithiaET::ArgumentError("--jit can only be used with run".to_string()).error())
+        }
+        if !program_args.is_empty() && command != Subcommand::Run {
+            return Err(LithiaET::ArgumentError("program arguments can only be passed with run".to_string()).error())
+        }
+        if target.is_some() && command != Subcommand::Build {
+            // the produced code could not be executed on this machine, check does not generate any
+            return Err(LithiaET::ArgumentError("--target can only be used with build".to_string()).error())
+        }
+        if crate_type.is_some() && command != Subcommand::Build {
+            return Err(LithiaET::ArgumentError("--crate-type can only be used with build".to_string()).error())
+        }
+        if opt_level.is_some() && !matches!(command, Subcommand::Build | Subcommand::Run | Subcommand::Test) {
+            return Err(LithiaET::ArgumentError("-O can only be used with build, run and test".to_string()).error())
+        }
+        if debug_info && !matches!(command, Subcommand::Build | Subcommand::Run | Subcommand::Test) {
+            return Err(LithiaET::ArgumentError("-g can only be used with build, run and test".to_string()).error())
+        }
+        let crate_type = crate_type.unwrap_or(CrateType::Bin);
+        if let Some(e) = emit.iter().find(|e| matches!(e, Emit::Exe | Emit::StaticLib | Emit::SharedLib) && **e != crate_type.artifact()) {
+            return Err(LithiaET::ArgumentError(format!("cannot emit {} with --crate-type={}", e.name(), crate_type.name())).error())
+        }
+        if emit.is_empty() && command == Subcommand::Build {
+            emit.push(crate_type.artifact())
+        }
+        if (command == Subcommand::Run && !jit || command == Subcommand::Test) && !emit.contains(&Emit::Exe) {
+            emit.push(Emit::Exe)
+        }
+        Ok(Self {
+            command,
+            input: match input {
+                Some(input) => input,
+                // the repl can be started without a file to load
+                None if command == Subcommand::Repl => PathBuf::new(),
+                None if command == Subcommand::Explain => return Err(LithiaET::ArgumentError("expected error code after --explain".to_string()).error()),
+                None => return Err(LithiaET::ArgumentError("expected input file".to_string()).error())
+            },
+            output,
+            emit,
+            crate_type,
+            target,
+            opt_level: opt_level.unwrap_or(OptLevel::O0),
+            debug_info,
+            jit,
+            verbosity,
+            error_format,
+            lint_levels,
+            program_args
+        })
+    }
+
+    fn parse_emit(kinds: &str) -> Result<Vec<Emit>, LithiaError> {
+        kinds.split(',').map(Emit::from_name).collect()
+    }
+
+    pub(crate) fn emits(&self, emit: Emit) -> bool {
+        self.emit.contains(&emit)
+    }
 
+    pub(crate) fn module_name(&self) -> String {
+        self.input.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or("main".to_string())
+    }
+
+    /// Path of the executable or library, the other artifacts are placed next to it.
+    pub(crate) fn exe_path(&self) -> PathBuf {
+        self.output.clone().unwrap_or_else(|| match (self.command, self.crate_type) {
+            // keeps the harness apart from the executable of a regular build
+            (Subcommand::Test, _) => self.input.with_extension("test"),
+            (_, CrateType::StaticLib | CrateType::SharedLib) => self.input.with_file_name(format!("{}{}", env::consts::DLL_PREFIX, self.module_name())),
+            _ => self.input.with_extension(Emit::Exe.extension())
+        })
+    }
+
+    /// Path of the artifact, placed next to the executable.
+    pub(crate) fn artifact_path(&self, emit: Emit) -> PathBuf {
+        match emit {
+            Emit::Exe => self.exe_path(),
+            emit => self.exe_path().with_extension(emit.extension())
+        }
+    }
+
+    fn write_artifact<F: FnOnce() -> String>(&self, emit: Emit, content: F) -> Result<(), LithiaError> {
+        if self.emits(emit) {
+            let path = self.artifact_path(emit);
+            fs::write(&path, content()).map_err(LithiaError::from).e_when(format!("writing {}", path.display()))?;
+            self.report_artifact(&path);
+        }
+        Ok(())
+    }
+
+    fn report_warnings(&self, warnings: &Diagnostics) {
+        if warnings.has_warnings() {
+            warnings.print(self.error_format)
+        }
+    }
+
+    fn report_artifact(&self, path: &Path) {
+        if self.verbosity >= 1 {
+            println!("wrote {}", path.display());
+        }
+    }
 }
 
-pub(crate) fn compile(args: Arguments) -> Result<(), LithiaError>{
-    let source = Source::from_file("examples/testing/mutations.li")?;
+/// Runs the compiler as configured by the arguments and returns the exit code of the process.
+pub(crate) fn compile(args: Arguments) -> Result<i32, Diagnostics>{
+    if args.command == Subcommand::Repl {
+        return repl(args)
+    }
+    if args.command == Subcommand::Explain {
+        return Ok(explain(&args.input.to_string_lossy())?)
+    }
+    let source = Source::from_file(args.input.to_string_lossy().to_string())?;
     let tokens = tokenize(source)?;
-    println!("{tokens:?}");
-    let module = parse(tokens, ("main".to_string(), None))?;
-    println!("{}", module.print());
-    let llvm_mod = build_llvm_ir(module)?;
-    build_exe(llvm_mod, env!("LLVM_SYS_150_PREFIX"), "examples/testing/mutations.bc", "examples/testing/mutations.exe",  true, true)?;
-    println!();
-    let code = Command::new("examples/testing/mutations.exe")
-        .spawn().unwrap().wait().unwrap();
-    println!("executed with {code}");
+    args.write_artifact(Emit::Tokens, || tokens.iter().map(|t| format!("{t:?}\n")).collect())?;
+    let module = parse(tokens, (args.module_name(), None))?;
+    args.write_artifact(Emit::Ast, || module.print())?;
+    if args.command == Subcommand::Check {
+        return Ok(0)
+    }
+    let target = Target::new(args.target.as_deref(), args.opt_level)?;
+    if args.command == Subcommand::Test {
+        let tests = collect_tests(&module)?;
+        let (llvm_mod, warnings) = build_test_harness(module, &tests, &target, args.debug_info, &args.lint_levels)?;
+        args.report_warnings(&warnings);
+        let r = target.optimize(llvm_mod, args.opt_level).and_then(|_| emit_llvm_artifacts(&args, llvm_mod, &target));
+        unsafe { core::LLVMDisposeModule(llvm_mod) }
+        r?;
+        return Ok(run_tests(&args, &tests)?)
+    }
+    let (llvm_mod, warnings) = build_llvm_ir(module, &target, args.crate_type.has_entrypoint(), args.debug_info, &args.lint_levels)?;
+    args.report_warnings(&warnings);
+    let r = target.optimize(llvm_mod, args.opt_level).and_then(|_| emit_llvm_artifacts(&args, llvm_mod, &target));
+    if args.jit && r.is_ok() {
+        let mut program_args = vec![args.input.to_string_lossy().to_string()];
+        program_args.extend(args.program_args.iter().cloned());
+        return Ok(Jit::new(llvm_mod)?.run_main(&program_args)?)
+    }
+    unsafe { core::LLVMDisposeModule(llvm_mod) }
+    r?;
+    if args.command == Subcommand::Run {
+        // a bare file name would otherwise be looked up in PATH
+        let code = Command::new(Path::new(".").join(args.exe_path()))
+            .args(&args.program_args)
+            .spawn()?.wait()?;
+        if args.verbosity >= 1 {
+            println!("executed with {code}");
+        }
+        return Ok(code.code().unwrap_or(1))
+    }
+    Ok(0)
+}
+
+fn emit_llvm_artifacts(args: &Arguments, module: LLVMModuleRef, target: &Target) -> Result<(), LithiaError> {
+    if args.emits(Emit::LlvmIr) {
+        let path = args.artifact_path(Emit::LlvmIr);
+        write_llvm_ir(module, &path)?;
+        args.report_artifact(&path);
+    }
+    if args.emits(Emit::Bitcode) {
+        let path = args.artifact_path(Emit::Bitcode);
+        write_bitcode(module, &path)?;
+        args.report_artifact(&path);
+    }
+    let linked = [Emit::Exe, Emit::StaticLib, Emit::SharedLib].into_iter().find(|e| args.emits(*e));
+    if args.emits(Emit::Asm) || args.emits(Emit::Object) || linked.is_some() {
+        if args.emits(Emit::Asm) {
+            let path = args.artifact_path(Emit::Asm);
+            target.emit_to_file(module, &path, LLVMCodeGenFileType::LLVMAssemblyFile)?;
+            args.report_artifact(&path);
+        }
+        if args.emits(Emit::Object) || linked.is_some() {
+            let path = args.artifact_path(Emit::Object);
+            target.emit_to_file(module, &path, LLVMCodeGenFileType::LLVMObjectFile)?;
+            if args.emits(Emit::Object) {
+                args.report_artifact(&path);
+            }
+        }
+    }
+    if let Some(emit) = linked {
+        let object_file = args.artifact_path(Emit::Object);
+        let output_file = args.artifact_path(emit);
+        match emit {
+            Emit::StaticLib => archive_static_lib(object_file.as_path(), output_file.as_path())?,
+            Emit::SharedLib => link_shared_lib(object_file.as_path(), output_file.as_path())?,
+            _ => link_exe(object_file.as_path(), output_file.as_path())?
+        }
+        if !args.emits(Emit::Object) {
+            fs::remove_file(object_file)?;
+        }
+        args.report_artifact(&output_file);
+    }
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,111 @@
+use std::fmt::{Display, Formatter};
+use std::mem;
+use crate::compiler::ErrorFormat;
+use crate::error::LithiaError;
+
+/// Errors and warnings collected during a compilation, so all problems of a file are reported at once
+/// instead of stopping at the first one.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Diagnostics {
+    errors: Vec<LithiaError>,
+    warnings: Vec<LithiaError>
+}
+
+impl Diagnostics {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn push(&mut self, error: LithiaError) {
+        if error.is_warning() {
+            self.warnings.push(error)
+        } else {
+            self.errors.push(error)
+        }
+    }
+
+    pub(crate) fn append(&mut self, mut other: Diagnostics) {
+        self.errors.append(&mut other.errors);
+        self.warnings.append(&mut other.warnings)
+    }
+
+    /// Whether there are no errors, warnings may have been collected.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub(crate) fn has_warnings(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+
+    /// The collected errors, ordered by the position they were found at.
+    pub(crate) fn errors(&self) -> Vec<&LithiaError> {
+        Self::sorted(&self.errors)
+    }
+
+    /// Warnings followed by errors, each ordered by position.
+    pub(crate) fn all(&self) -> Vec<&LithiaError> {
+        let mut all = Self::sorted(&self.warnings);
+        all.append(&mut self.errors());
+        all
+    }
+
+    fn sorted(diagnostics: &[LithiaError]) -> Vec<&LithiaError> {
+        let mut sorted = diagnostics.iter().collect::<Vec<_>>();
+        // diagnostics without a location come last
+        sorted.sort_by_key(|e| e.labels().first().map(|l| (0, format!("{:?}", l.span.source), l.span.start)).unwrap_or((1, String::new(), 0)));
+        sorted
+    }
+
+    /// Fails with the diagnostics collected so far if there are errors,
+    /// used where later stages would only report follow-up errors.
+    pub(crate) fn check(&mut self) -> Result<(), Diagnostics> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(mem::take(self))
+        }
+    }
+
+    /// Prints all diagnostics, as one JSON object per line for `--error-format=json`.
+    pub(crate) fn print(&self, format: ErrorFormat) {
+        match format {
+            ErrorFormat::Human => println!("{self}"),
+            ErrorFormat::Json => for diagnostic in self.all() {
+                println!("{}", diagnostic.to_json())
+            }
+        }
+    }
+}
+
+impl From<LithiaError> for Diagnostics {
+    fn from(error: LithiaError) -> Self {
+        let mut diagnostics = Self::new();
+        diagnostics.push(error);
+        diagnostics
+    }
+}
+
+impl From<std::io::Error> for Diagnostics {
+    fn from(error: std::io::Error) -> Self {
+        LithiaError::from(error).into()
+    }
+}
+
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut summary = vec![];
+        let warnings = self.warnings.len();
+        if warnings > 0 {
+            summary.push(format!("{warnings} warning{} emitted", if warnings == 1 { "" } else { "s" }));
+        }
+        let errors = self.errors.len();
+        if errors > 0 {
+            summary.push(format!("aborting due to {errors} error{}", if errors == 1 { "" } else { "s" }));
+        }
+        for diagnostic in self.all() {
+            write!(f, "{diagnostic}\n\n")?;
+        }
+        write!(f, "{}", summary.join("\n"))
+    }
+}
@@ -1,15 +1,22 @@
 use std::fmt::{Display, Formatter};
+use std::rc::Rc;
 use crate::ast::code_printer::CodePrinter;
 use crate::ast::Type;
 use crate::ast::types_impl::TySat;
+use crate::lints::{Lint, LintLevel};
+use crate::source::snippet::{render, Label};
 use crate::source::span::Span;
 use crate::tokens::{Literal, NumLit};
+use crate::util::ansi::{paint, Style};
+use crate::util::json;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct LithiaError {
     et: LithiaET,
-    locs: Vec<Span>,
-    context: Vec<String>
+    labels: Vec<Label>,
+    context: Vec<String>,
+    /// `note` and `help` lines shown below the code
+    notes: Vec<(&'static str, String)>
 }
 
 impl LithiaError {
@@ -18,108 +25,241 @@ impl LithiaError {
         self
     }
     pub(crate) fn at(mut self, loc: Span) -> Self{
-        self.locs = vec![loc];
+        self.labels = vec![Label::new(loc)];
         self
     }
     pub(crate) fn at_add(mut self, loc: Span) -> Self{
-        self.locs.push(loc);
+        self.labels.push(Label::new(loc));
         self
     }
     pub(crate) fn ats(mut self, locs: Vec<Span>) -> Self{
-        self.locs = locs;
+        self.labels = locs.into_iter().map(Label::new).collect();
         self
     }
+    /// Adds a location with a message, the first location is the primary one.
+    pub(crate) fn at_label<T: Into<String>>(mut self, loc: Span, label: T) -> Self{
+        self.labels.push(Label::with_text(loc, label));
+        self
+    }
+    pub(crate) fn note<T: Into<String>>(mut self, note: T) -> Self{
+        self.notes.push(("note", note.into()));
+        self
+    }
+    pub(crate) fn help<T: Into<String>>(mut self, help: T) -> Self{
+        self.notes.push(("help", help.into()));
+        self
+    }
+    pub(crate) fn labels(&self) -> &[Label]{
+        &self.labels
+    }
+    pub(crate) fn code(&self) -> &'static str{
+        self.et.code()
+    }
+    /// Lints below `deny` do not fail the compilation.
+    pub(crate) fn is_warning(&self) -> bool{
+        matches!(self.et, LithiaET::Lint(_, LintLevel::Warn, _))
+    }
+    /// Whether the input ended before what was being read was complete.
+    pub(crate) fn is_eof(&self) -> bool{
+        matches!(self.et, LithiaET::EOF)
+    }
+    /// Name of the `LithiaET` variant.
+    pub(crate) fn kind(&self) -> &'static str{
+        self.et.kind()
+    }
+    /// The error as a single line JSON object for `--error-format=json`.
+    /// Lines and columns start at 1, byte and column ends are exclusive.
+    pub(crate) fn to_json(&self) -> String{
+        let (title, message) = self.et.describe();
+        let spans = self.labels.iter().enumerate().map(|(i, label)| {
+            let (sl, sc) = label.span.start().pos();
+            let (el, ec) = label.span.end().pos();
+            json::object(&[
+                ("file", json::string(&format!("{:?}", label.span.source))),
+                ("byte_start", label.span.start.to_string()),
+                ("byte_end", label.span.source.char_end(label.span.end).to_string()),
+                ("line_start", sl.to_string()),
+                ("column_start", (sc + 1).to_string()),
+                ("line_end", el.to_string()),
+                ("column_end", (ec + 2).to_string()),
+                ("primary", (i == 0).to_string()),
+                ("label", label.text.as_deref().map(json::string).unwrap_or("null".to_string()))
+            ])
+        });
+        let notes = self.notes.iter().map(|(kind, text)| json::object(&[
+            ("kind", json::string(kind)),
+            ("message", json::string(text))
+        ]));
+        let lint = match &self.et {
+            LithiaET::Lint(lint, ..) => json::string(lint.name()),
+            _ => "null".to_string()
+        };
+        json::object(&[
+            ("kind", json::string(self.kind())),
+            ("level", json::string(if self.is_warning() { "warning" } else { "error" })),
+            ("lint", lint),
+            ("code", json::string(self.code())),
+            ("title", json::string(&title)),
+            ("message", json::string(&message)),
+            ("context", json::array(self.context.iter().map(|c| json::string(c)))),
+            ("spans", json::array(spans)),
+            ("notes", json::array(notes))
+        ])
+    }
 }
 
 impl From<std::io::Error> for LithiaError {
     fn from(error: std::io::Error) -> Self {
-        LithiaET::IOError(error).error().when("doing IO operation")
+        LithiaET::IOError(Rc::new(error)).error().when("doing IO operation")
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum LithiaET {
     EOF,
-    IOError(std::io::Error),
+    ArgumentError(String),
+    IOError(Rc<std::io::Error>),
     TokenizationError(String),
     LiteralError(Literal, String),
     ParsingError(String),
     CompilationError(String),
+    LLVMError(String),
+    VerificationError(String, String),
+    LinkError(String),
     AlreadyDefinedError(String, String),
     VariableNotFound(String),
+    NotFoundInModule(String, String),
     TypeError(Type, Type),
     CastError(Type, Type),
     TagError(String),
     UnsafeError(String),
+    Lint(Lint, LintLevel, String),
 }
 
 impl LithiaET {
     pub(crate) fn error(self) -> LithiaError {
         LithiaError {
             et: self,
-            locs: vec![],
-            context: vec![]
+            labels: vec![],
+            context: vec![],
+            notes: vec![]
         }
     }
     pub(crate) fn at(self, loc: Span) -> LithiaError {
         LithiaError {
             et: self,
-            locs: vec![loc],
-            context: vec![]
+            labels: vec![Label::new(loc)],
+            context: vec![],
+            notes: vec![]
         }
     }
+    pub(crate) fn at_label<T: Into<String>>(self, loc: Span, label: T) -> LithiaError {
+        self.error().at_label(loc, label)
+    }
     pub(crate) fn ats(self, locs: Vec<Span>) -> LithiaError {
         LithiaError {
             et: self,
-            locs,
-            context: vec![]
+            labels: locs.into_iter().map(Label::new).collect(),
+            context: vec![],
+            notes: vec![]
+        }
+    }
+}
+
+impl LithiaET {
+    /// Stable identifier of the error, explained by `lithia --explain <code>`.
+    /// Codes are never reused, new variants get the next free number.
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            LithiaET::EOF => "L0001",
+            LithiaET::ArgumentError(_) => "L0002",
+            LithiaET::IOError(_) => "L0003",
+            LithiaET::TokenizationError(_) => "L0004",
+            LithiaET::LiteralError(..) => "L0005",
+            LithiaET::ParsingError(_) => "L0006",
+            LithiaET::CompilationError(_) => "L0007",
+            LithiaET::LLVMError(_) => "L0008",
+            LithiaET::VerificationError(..) => "L0009",
+            LithiaET::LinkError(_) => "L0010",
+            LithiaET::AlreadyDefinedError(..) => "L0011",
+            LithiaET::VariableNotFound(_) => "L0012",
+            LithiaET::NotFoundInModule(..) => "L0013",
+            LithiaET::TypeError(..) => "L0014",
+            LithiaET::CastError(..) => "L0015",
+            LithiaET::TagError(_) => "L0016",
+            LithiaET::UnsafeError(_) => "L0017",
+            LithiaET::Lint(..) => "L0018",
+        }
+    }
+
+    /// Name of the variant, used by `--error-format=json` and the tests.
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            LithiaET::EOF => "EOF",
+            LithiaET::ArgumentError(_) => "ArgumentError",
+            LithiaET::IOError(_) => "IOError",
+            LithiaET::TokenizationError(_) => "TokenizationError",
+            LithiaET::LiteralError(..) => "LiteralError",
+            LithiaET::ParsingError(_) => "ParsingError",
+            LithiaET::CompilationError(_) => "CompilationError",
+            LithiaET::LLVMError(_) => "LLVMError",
+            LithiaET::VerificationError(..) => "VerificationError",
+            LithiaET::LinkError(_) => "LinkError",
+            LithiaET::AlreadyDefinedError(..) => "AlreadyDefinedError",
+            LithiaET::VariableNotFound(_) => "VariableNotFound",
+            LithiaET::NotFoundInModule(..) => "NotFoundInModule",
+            LithiaET::TypeError(..) => "TypeError",
+            LithiaET::CastError(..) => "CastError",
+            LithiaET::TagError(_) => "TagError",
+            LithiaET::UnsafeError(_) => "UnsafeError",
+            LithiaET::Lint(..) => "Lint",
+        }
+    }
+
+    /// Title and message of the error.
+    fn describe(&self) -> (String, String) {
+        match self {
+            LithiaET::EOF => ("Input Error".to_string(), "reached end of file".to_string()),
+            LithiaET::ArgumentError(e) => ("Argument Error".to_string(), e.clone()),
+            LithiaET::IOError(e) => ("IO Error".to_string(), e.to_string()),
+            LithiaET::TokenizationError(e) => ("Tokenization Error".to_string(), e.clone()),
+            LithiaET::LiteralError(lit, e) => (format!("{} literal Error", match lit {
+                Literal::String(_) => "String",
+                Literal::Char(_) => "Char",
+                Literal::Number(NumLit::Integer(_), _) => "Integer",
+                Literal::Number(NumLit::Float(_), _) => "Float",
+                Literal::Bool(_) => "Float",
+                Literal::Array(..) => "Array"
+            }), e.clone()),
+            LithiaET::ParsingError(e) => ("Parsing Error".to_string(), e.clone()),
+            LithiaET::CompilationError(e) => ("Compilation Error".to_string(), e.clone()),
+            LithiaET::LLVMError(e) => ("LLVM Error".to_string(), e.clone()),
+            LithiaET::VerificationError(func, e) => ("Verification Error".to_string(), format!("generated invalid LLVM IR for {func}:\n    {}", e.replace('\n', "\n    "))),
+            LithiaET::LinkError(e) => ("Link Error".to_string(), e.clone()),
+            LithiaET::AlreadyDefinedError(what, name) => ("Multiple definitions Error".to_string(), format!("{} {} was already defined", what, name)),
+            LithiaET::VariableNotFound(ident) => ("Name Error".to_string(), format!("could not find variable {ident}")),
+            LithiaET::NotFoundInModule(name, module) => ("Name Error".to_string(), format!("could not find {name} in module {module}")),
+            LithiaET::TypeError(expected, found) => ("Type Error".to_string(), format!("expected {}, found {}", expected.print(), found.print())),
+            LithiaET::CastError(expected, found) => ("Cast Error".to_string(), format!("cannot cast from {} to {}", expected.print(), found.print())),
+            LithiaET::TagError(err) => ("Compiler Flag Error".to_string(), err.clone()),
+            LithiaET::UnsafeError(thing) => ("Unsafe Context Error".to_string(), format!("cannot use {thing} in safe context")),
+            LithiaET::Lint(_, level, message) => (if *level == LintLevel::Deny { "Lint Error" } else { "Warning" }.to_string(), message.clone()),
         }
     }
 }
 
 impl Display for LithiaError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}{}{}",
-           match &self.et {
-               LithiaET::EOF => format!("Input Error:\n    reached end of file"),
-               LithiaET::IOError(e) => format!("IO Error:\n    {}", e),
-               LithiaET::TokenizationError(e) => format!("Tokenization Error:\n    {}", e),
-               LithiaET::LiteralError(lit, e) => format!("{} literal Error:\n    {}", match lit {
-                   Literal::String(_) => "String",
-                   Literal::Char(_) => "Char",
-                   Literal::Number(NumLit::Integer(_), _) => "Integer",
-                   Literal::Number(NumLit::Float(_), _) => "Float",
-                   Literal::Bool(_) => "Float",
-                   Literal::Array(..) => "Array"
-               }, e),
-               LithiaET::ParsingError(e) => format!("Parsing Error:\n    {}", e),
-               LithiaET::CompilationError(e) => format!("Compilation Error:\n    {}", e),
-               LithiaET::AlreadyDefinedError(what, name) =>
-                   format!("Multiple definitions Error:\n    {} {} was already defined",
-                   what, name),
-               LithiaET::VariableNotFound(ident) => format!("Name Error:\n    could not find variable {ident}"),
-               LithiaET::TypeError(expected, found) => format!("Type Error:\n    expected {} found {}:", expected.print(), found.print()),
-               LithiaET::CastError(expected, found) => format!("Cast Error:\n    cannot cast from {} to {}:", expected.print(), found.print()),
-               LithiaET::TagError(err) => format!("Compiler Flag Error:\n    {err}"),
-               LithiaET::UnsafeError(thing) => format!("Unsafe Context Error:\n    cannot use {thing} in safe context.\n    tag the expr or func with #[unsafe]"),
-           },
-           if self.context.len() > 0 {
-               format!("\n    while {}", self.context.join("\n    while "))
-           } else {
-               String::new()
-           },
-           {
-               let mut locs = String::new();
-               for loc in &self.locs {
-                   locs.push_str(&format!("\n{:?}: {:?}\n{}",
-                                          loc.source,
-                                          loc,
-                                          loc.render_span_code(2)
-                   ))
-               }
-               locs
-           }
-        )
+        let (title, message) = self.et.describe();
+        let style = if self.is_warning() { Style::Yellow } else { Style::Red };
+        write!(f, "{}: {}", paint(format!("{title}[{}]", self.et.code()), style), paint(message, Style::Bold))?;
+        let notes = self.context.iter().map(|c| ("note", format!("while {c}")))
+            .chain(self.notes.iter().cloned())
+            .collect::<Vec<_>>();
+        if !self.labels.is_empty() || !notes.is_empty() {
+            write!(f, "\n{}", render(&self.labels, &notes, style))?;
+        }
+        Ok(())
     }
 }
 
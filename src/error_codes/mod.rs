@@ -0,0 +1,34 @@
+use crate::error::{LithiaError, LithiaET};
+
+/// Long-form explanation of an error code, with a failing and a fixed example.
+pub(crate) fn explanation(code: &str) -> Option<&'static str> {
+    Some(match code {
+        "L0001" => include_str!("L0001.md"),
+        "L0002" => include_str!("L0002.md"),
+        "L0003" => include_str!("L0003.md"),
+        "L0004" => include_str!("L0004.md"),
+        "L0005" => include_str!("L0005.md"),
+        "L0006" => include_str!("L0006.md"),
+        "L0007" => include_str!("L0007.md"),
+        "L0008" => include_str!("L0008.md"),
+        "L0009" => include_str!("L0009.md"),
+        "L0010" => include_str!("L0010.md"),
+        "L0011" => include_str!("L0011.md"),
+        "L0012" => include_str!("L0012.md"),
+        "L0013" => include_str!("L0013.md"),
+        "L0014" => include_str!("L0014.md"),
+        "L0015" => include_str!("L0015.md"),
+        "L0016" => include_str!("L0016.md"),
+        "L0017" => include_str!("L0017.md"),
+        "L0018" => include_str!("L0018.md"),
+        _ => return None
+    })
+}
+
+/// Prints the explanation of `lithia --explain <code>`, the code is matched case-insensitively.
+pub(crate) fn explain(code: &str) -> Result<i32, LithiaError> {
+    let text = explanation(&code.to_uppercase())
+        .ok_or_else(|| LithiaET::ArgumentError(format!("unknown error code '{code}', expected a code like L0001")).error())?;
+    println!("{}", text.trim_end());
+    Ok(0)
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use crate::error::{LithiaError, LithiaET};
+use crate::source::span::Span;
+
+/// Checks reported as warnings, their level is set by `-A`, `-W` and `-D` or by
+/// `#[allow(...)]`, `#[warn(...)]` and `#[deny(...)]` tags on functions and expressions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Lint {
+    UnusedVariables,
+    UnusedResults,
+    UnusedUnsafe,
+    UnreachableCode,
+    ShadowedBindings
+}
+
+impl Lint {
+    pub(crate) const ALL: [Lint; 5] = [Lint::UnusedVariables, Lint::UnusedResults, Lint::UnusedUnsafe, Lint::UnreachableCode, Lint::ShadowedBindings];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedVariables => "unused_variables",
+            Lint::UnusedResults => "unused_results",
+            Lint::UnusedUnsafe => "unused_unsafe",
+            Lint::UnreachableCode => "unreachable_code",
+            Lint::ShadowedBindings => "shadowed_bindings"
+        }
+    }
+
+    pub(crate) fn default_level(self) -> LintLevel {
+        match self {
+            // shadowing is a common way to transform a value step by step
+            Lint::ShadowedBindings => LintLevel::Allow,
+            // most results of C functions like printf are not needed
+            Lint::UnusedResults => LintLevel::Allow,
+            _ => LintLevel::Warn
+        }
+    }
+
+    /// The lints named by a lint or a group, `unused` are all `unused_*` lints
+    /// and `warnings` are all lints which warn by default.
+    pub(crate) fn from_name(name: &str) -> Option<Vec<Lint>> {
+        match name {
+            "unused" => Some(vec![Lint::UnusedVariables, Lint::UnusedResults, Lint::UnusedUnsafe]),
+            "warnings" => Some(Lint::ALL.into_iter().filter(|l| l.default_level() == LintLevel::Warn).collect()),
+            name => Lint::ALL.into_iter().find(|l| l.name() == name).map(|l| vec![l])
+        }
+    }
+
+    pub(crate) fn names() -> String {
+        Lint::ALL.iter().map(|l| l.name()).chain(["unused", "warnings"]).collect::<Vec<_>>().join(", ")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum LintLevel {
+    Allow,
+    Warn,
+    Deny
+}
+
+impl LintLevel {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            LintLevel::Allow => "allow",
+            LintLevel::Warn => "warn",
+            LintLevel::Deny => "deny"
+        }
+    }
+
+    /// The level set by the command line flags `-A`, `-W` and `-D`.
+    pub(crate) fn from_flag(flag: &str) -> Option<Self> {
+        match flag {
+            "-A" => Some(LintLevel::Allow),
+            "-W" => Some(LintLevel::Warn),
+            "-D" => Some(LintLevel::Deny),
+            _ => None
+        }
+    }
+
+    pub(crate) fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "allow" => Some(LintLevel::Allow),
+            "warn" => Some(LintLevel::Warn),
+            "deny" => Some(LintLevel::Deny),
+            _ => None
+        }
+    }
+}
+
+/// Where the level of a lint was set, mentioned in its diagnostic.
+#[derive(Debug, Clone)]
+pub(crate) enum LevelSource {
+    Default,
+    CommandLine(String),
+    Tag(Span)
+}
+
+/// Levels set on the command line, later flags override earlier ones.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LintLevels(HashMap<Lint, (LintLevel, String)>);
+
+impl LintLevels {
+    /// Applies a flag like `-D unused`.
+    pub(crate) fn set(&mut self, level: LintLevel, flag: &str, name: &str) -> Result<(), LithiaError> {
+        let lints = Lint::from_name(name)
+            .ok_or_else(|| LithiaET::ArgumentError(format!("unknown lint '{name}' for {flag}, expected one of {}", Lint::names())).error())?;
+        for lint in lints {
+            self.0.insert(lint, (level, format!("{flag} {name}")));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn get(&self, lint: Lint) -> (LintLevel, LevelSource) {
+        match self.0.get(&lint) {
+            Some((level, flag)) => (*level, LevelSource::CommandLine(flag.clone())),
+            None => (lint.default_level(), LevelSource::Default)
+        }
+    }
+}
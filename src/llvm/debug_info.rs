@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::ffi::c_uint;
+use std::path::Path;
+use std::ptr;
+use llvm_sys::{core, debuginfo, target, LLVMModuleFlagBehavior};
+use llvm_sys::debuginfo::{LLVMDIFlagZero, LLVMDWARFEmissionKind, LLVMDWARFSourceLanguage};
+use llvm_sys::prelude::{LLVMDIBuilderRef, LLVMMetadataRef, LLVMValueRef};
+use crate::ast::{Func, Ident, Ty, Type};
+use crate::ast::code_printer::CodePrinter;
+use crate::error::LithiaError;
+use crate::llvm::{LLVMModGenEnv, Variable};
+use crate::source::{CodePoint, Source};
+use crate::source::span::Span;
+
+const DW_ATE_BOOLEAN: c_uint = 0x02;
+const DW_ATE_SIGNED: c_uint = 0x05;
+const DW_ATE_UNSIGNED: c_uint = 0x08;
+
+/// Debug info of a module, one compile unit is created for every source file functions are defined in.
+pub(crate) struct DebugInfo {
+    units: HashMap<String, DebugUnit>,
+    /// source and subprogram of the functions being built
+    scopes: Vec<(String, LLVMMetadataRef)>
+}
+
+/// A DIBuilder can only create a single compile unit, so every unit has its own.
+struct DebugUnit {
+    builder: LLVMDIBuilderRef,
+    file: LLVMMetadataRef
+}
+
+fn source_key(source: &Source) -> String {
+    format!("{source:?}")
+}
+
+fn line_col(loc: &Span) -> (c_uint, c_uint) {
+    let (line, col) = CodePoint(loc.source.clone(), loc.start).pos();
+    (line as c_uint, col as c_uint + 1)
+}
+
+impl LLVMModGenEnv {
+    /// Emits DWARF debug info for everything built from now on.
+    pub(crate) fn enable_debug_info(&mut self) {
+        unsafe {
+            for (key, value) in [("Debug Info Version", debuginfo::LLVMDebugMetadataVersion()), ("Dwarf Version", 4)] {
+                let value = core::LLVMValueAsMetadata(core::LLVMConstInt(core::LLVMInt32Type(), value as u64, 0));
+                core::LLVMAddModuleFlag(self.module, LLVMModuleFlagBehavior::LLVMModuleFlagBehaviorWarning,
+                                        key.as_ptr() as *const _, key.len(), value);
+            }
+        }
+        self.debug = Some(DebugInfo {
+            units: HashMap::new(),
+            scopes: vec![]
+        })
+    }
+
+    /// Finalizes the debug info, has to be called before the module is handed out.
+    pub(crate) fn finish_debug_info(&mut self) {
+        if let Some(debug) = self.debug.take() {
+            for unit in debug.units.values() {
+                unsafe {
+                    debuginfo::LLVMDIBuilderFinalize(unit.builder);
+                    debuginfo::LLVMDisposeDIBuilder(unit.builder);
+                }
+            }
+        }
+    }
+
+    fn debug_unit(&mut self, source: &Source) -> Option<&DebugUnit> {
+        let module = self.module;
+        let debug = self.debug.as_mut()?;
+        Some(debug.units.entry(source_key(source)).or_insert_with(|| unsafe {
+            let path = source.path().map(Path::to_path_buf).unwrap_or_default();
+            let path = path.canonicalize().unwrap_or(path);
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or(source_key(source));
+            let dir = path.parent().map(|d| d.to_string_lossy().to_string()).unwrap_or_default();
+            let builder = debuginfo::LLVMCreateDIBuilder(module);
+            let file = debuginfo::LLVMDIBuilderCreateFile(builder, name.as_ptr() as *const _, name.len(), dir.as_ptr() as *const _, dir.len());
+            let producer = "lithia";
+            debuginfo::LLVMDIBuilderCreateCompileUnit(builder, LLVMDWARFSourceLanguage::LLVMDWARFSourceLanguageC, file,
+                                                      producer.as_ptr() as *const _, producer.len(), 0,
+                                                      ptr::null(), 0, 0, ptr::null(), 0,
+                                                      LLVMDWARFEmissionKind::LLVMDWARFEmissionKindFull,
+                                                      0, 1, 0, ptr::null(), 0, ptr::null(), 0);
+            DebugUnit {
+                builder,
+                file
+            }
+        }))
+    }
+
+    fn di_type(&mut self, builder: LLVMDIBuilderRef, ty: &Type) -> Result<LLVMMetadataRef, LithiaError> {
+        let name = ty.print();
+        unsafe {
+            let pointer_size = target::LLVMPointerSize(target::LLVMGetModuleDataLayout(self.module)) as u64 * 8;
+            Ok(match &ty.0 {
+                Ty::Single(_, item) => {
+                    let llvm_type = ty.llvm_type(self)?;
+                    let size = target::LLVMSizeOfTypeInBits(target::LLVMGetModuleDataLayout(self.module), llvm_type);
+                    let encoding = match item.0[0].0.chars().next() {
+                        _ if item.0[0].0 == "bool" => DW_ATE_BOOLEAN,
+                        Some('i') => DW_ATE_SIGNED,
+                        _ => DW_ATE_UNSIGNED
+                    };
+                    debuginfo::LLVMDIBuilderCreateBasicType(builder, name.as_ptr() as *const _, name.len(), size, encoding, LLVMDIFlagZero)
+                }
+                Ty::RawPointer => debuginfo::LLVMDIBuilderCreatePointerType(builder, ptr::null_mut(), pointer_size, 0, 0, name.as_ptr() as *const _, name.len()),
+                Ty::Pointer(inner) => {
+                    let inner = self.di_type(builder, inner)?;
+                    debuginfo::LLVMDIBuilderCreatePointerType(builder, inner, pointer_size, 0, 0, name.as_ptr() as *const _, name.len())
+                }
+                Ty::Array(inner, _) | Ty::Slice(inner) => {
+                    let count = if let Ty::Array(_, count) = &ty.0 { *count as i64 } else { 0 };
+                    let llvm_type = ty.llvm_type(self)?;
+                    let size = target::LLVMSizeOfTypeInBits(target::LLVMGetModuleDataLayout(self.module), llvm_type);
+                    let inner = self.di_type(builder, inner)?;
+                    let mut subscripts = [debuginfo::LLVMDIBuilderGetOrCreateSubrange(builder, 0, count)];
+                    debuginfo::LLVMDIBuilderCreateArrayType(builder, size, 0, inner, subscripts.as_mut_ptr(), 1)
+                }
+                Ty::Tuple(_) | Ty::Signature(..) => debuginfo::LLVMDIBuilderCreateUnspecifiedType(builder, name.as_ptr() as *const _, name.len())
+            })
+        }
+    }
+
+    /// Attaches a subprogram to the function, locations built until `end_debug_function` are scoped to it.
+    pub(crate) fn begin_debug_function(&mut self, func: &Func, function: LLVMValueRef) -> Result<(), LithiaError> {
+        let Some(unit) = self.debug_unit(&func.loc.source) else { return Ok(()) };
+        let (builder, file) = (unit.builder, unit.file);
+        let mut types = vec![if func.ret.0.is_empty() { ptr::null_mut() } else { self.di_type(builder, &func.ret)? }];
+        for (_, ty) in &func.args {
+            types.push(self.di_type(builder, ty)?);
+        }
+        let (line, _) = line_col(&func.name.1);
+        unsafe {
+            let function_type = debuginfo::LLVMDIBuilderCreateSubroutineType(builder, file, types.as_mut_ptr(), types.len() as c_uint, LLVMDIFlagZero);
+            let mut len = 0;
+            let linkage_name = core::LLVMGetValueName2(function, &mut len);
+            let subprogram = debuginfo::LLVMDIBuilderCreateFunction(builder, file, func.name.0.as_ptr() as *const _, func.name.0.len(),
+                                                                    linkage_name, len, file, line, function_type, 0, 1, line, LLVMDIFlagZero, 0);
+            debuginfo::LLVMSetSubprogram(function, subprogram);
+            self.debug.as_mut().unwrap().scopes.push((source_key(&func.loc.source), subprogram));
+            self.set_debug_loc(&func.name.1);
+            for (i, (ident, ty)) in func.args.iter().enumerate() {
+                let di_type = self.di_type(builder, ty)?;
+                let (line, _) = line_col(&ident.1);
+                let variable = debuginfo::LLVMDIBuilderCreateParameterVariable(builder, subprogram, ident.0.as_ptr() as *const _, ident.0.len(),
+                                                                               i as c_uint + 1, file, line, di_type, 1, LLVMDIFlagZero);
+                self.insert_debug_variable(builder, variable, core::LLVMGetParam(function, i as c_uint), false, &ident.1);
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn end_debug_function(&mut self) {
+        if let Some(debug) = self.debug.as_mut() {
+            debug.scopes.pop();
+        }
+    }
+
+    /// Sets the location of the instructions built from now on, returns the previous one to restore it later.
+    pub(crate) fn set_debug_loc(&mut self, loc: &Span) -> Option<LLVMMetadataRef> {
+        let (_, scope) = self.debug.as_ref()?.scopes.last()?;
+        let (line, col) = line_col(loc);
+        unsafe {
+            let previous = core::LLVMGetCurrentDebugLocation2(self.builder);
+            let location = debuginfo::LLVMDIBuilderCreateDebugLocation(core::LLVMGetGlobalContext(), line, col, *scope, ptr::null_mut());
+            core::LLVMSetCurrentDebugLocation2(self.builder, location);
+            Some(previous)
+        }
+    }
+
+    pub(crate) fn restore_debug_loc(&mut self, previous: Option<LLVMMetadataRef>) {
+        if let Some(previous) = previous {
+            unsafe { core::LLVMSetCurrentDebugLocation2(self.builder, previous) }
+        }
+    }
+
+    /// Describes a local variable created by `let`.
+    pub(crate) fn debug_variable(&mut self, name: &Ident, var: &Variable) -> Result<(), LithiaError> {
+        let Some((key, scope)) = self.debug.as_ref().and_then(|d| d.scopes.last().cloned()) else { return Ok(()) };
+        let unit = &self.debug.as_ref().unwrap().units[&key];
+        let (builder, file) = (unit.builder, unit.file);
+        let di_type = self.di_type(builder, &var.ast_type)?;
+        let (line, _) = line_col(&name.1);
+        unsafe {
+            let variable = debuginfo::LLVMDIBuilderCreateAutoVariable(builder, scope, name.0.as_ptr() as *const _, name.0.len(),
+                                                                      file, line, di_type, 1, LLVMDIFlagZero, 0);
+            self.insert_debug_variable(builder, variable, var.llvm_value, var.mutable, &name.1);
+        }
+        Ok(())
+    }
+
+    /// Mutable variables live in an alloca and are declared once, others are described by their value.
+    unsafe fn insert_debug_variable(&mut self, builder: LLVMDIBuilderRef, variable: LLVMMetadataRef, value: LLVMValueRef, in_memory: bool, loc: &Span) {
+        let Some((_, scope)) = self.debug.as_ref().and_then(|d| d.scopes.last().cloned()) else { return };
+        let (line, col) = line_col(loc);
+        let location = debuginfo::LLVMDIBuilderCreateDebugLocation(core::LLVMGetGlobalContext(), line, col, scope, ptr::null_mut());
+        let expr = debuginfo::LLVMDIBuilderCreateExpression(builder, ptr::null_mut(), 0);
+        let block = core::LLVMGetInsertBlock(self.builder);
+        if in_memory {
+            debuginfo::LLVMDIBuilderInsertDeclareAtEnd(builder, value, variable, expr, location, block);
+        } else {
+            debuginfo::LLVMDIBuilderInsertDbgValueAtEnd(builder, value, variable, expr, location, block);
+        }
+    }
+}
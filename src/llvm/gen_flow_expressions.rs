@@ -6,8 +6,8 @@ use llvm_sys::core;
 use crate::ast::code_printer::CodePrinter;
 
 /// How to: cook an if
-/// 1. create blocks, save reference to start_block
-/// 2. build condition expression
+/// 1. create blocks
+/// 2. build condition expression, save reference to the block it ends in as start_block
 /// 2. goto then_block, build block body
 /// 3. goto else_block, build block body
 /// 4. resolve all types, check for compatability, test if both branches resolve to a value
@@ -18,13 +18,13 @@ use crate::ast::code_printer::CodePrinter;
 /// 5. goto then_block, build jmp continue_block
 /// 6. goto else_block, build jmp continue_block
 /// 7. goto start_block, build break
-/// 8. goto continue_block
+/// 8. goto continue_block, build unreachable if both branches return
 pub(crate) fn compile_if(cond: &Expression, body: &Block, else_body: &Block, env: &mut LLVMModGenEnv, ret_name: Option<String>) -> Result<ReturnInfo, LithiaError> {
     let then_block = unsafe { core::LLVMAppendBasicBlock(env.function.unwrap(), c_str_ptr!("then")) };
-    let start_block = unsafe { core::LLVMGetPreviousBasicBlock(then_block) };
     let else_block = unsafe { core::LLVMAppendBasicBlock(env.function.unwrap(), c_str_ptr!("else")) };
     let continue_block = unsafe { core::LLVMAppendBasicBlock(env.function.unwrap(), c_str_ptr!("ifcont")) };
     let c = cond.build(env, None)?;
+    let start_block = unsafe { core::LLVMGetInsertBlock(env.builder) };
     unsafe {
         core::LLVMPositionBuilderAtEnd(env.builder, then_block); // START THEN CLAUSE
     };
@@ -44,7 +44,8 @@ pub(crate) fn compile_if(cond: &Expression, body: &Block, else_body: &Block, env
         (Some(t1), Some(t2)) => {
             if t1.0 != t2.0 {
                 return Err(LithiaET::TypeError(t1.0.clone(), t2.0.clone())
-                    .ats(vec![t1.0.1.clone(), t2.0.1.clone()]))
+                    .at_label(t2.0.1.clone(), format!("else returns {}", t2.0.print()))
+                    .at_label(t1.0.1.clone(), format!("if returns {}", t1.0.print())))
             }
             Some(t1)
         }
@@ -52,7 +53,8 @@ pub(crate) fn compile_if(cond: &Expression, body: &Block, else_body: &Block, env
     if let Some(rt) = &ret_t && let Some(r) = &c.return_t {
         if rt.0 != r.0 {
             return Err(LithiaET::TypeError(rt.0.clone(), r.0.clone())
-                .ats(vec![rt.0.1.clone(), r.0.1.clone()]))
+                .at_label(r.0.1.clone(), format!("found {} here", r.0.print()))
+                .at_label(rt.0.1.clone(), format!("expected {} here", rt.0.print())))
         }
     }
     let v = match match (body_r.variable.clone(), else_body_r.variable.clone()) {
@@ -61,7 +63,8 @@ pub(crate) fn compile_if(cond: &Expression, body: &Block, else_body: &Block, env
         (Some(v1), Some(v2)) => {
             if v1.ast_type != v2.ast_type {
                 return Err(LithiaET::TypeError(v1.ast_type.clone(), v2.ast_type.clone())
-                    .ats(vec![v1.ast_type.1.clone(), v2.ast_type.1.clone()]))
+                    .at_label(v2.ast_type.1.clone(), format!("else resolves to {}", v2.ast_type.print()))
+                    .at_label(v1.ast_type.1.clone(), format!("if resolves to {}", v1.ast_type.print())))
             }
             Some(v1)
         }
@@ -103,6 +106,10 @@ pub(crate) fn compile_if(cond: &Expression, body: &Block, else_body: &Block, env
         core::LLVMPositionBuilderAtEnd(env.builder, start_block);
         core::LLVMBuildCondBr(env.builder, c.resolve_var()?.llvm_value, then_block, else_block); // IF CONDITION CALL
         core::LLVMPositionBuilderAtEnd(env.builder, continue_block);
+        if returns == (true, true) && v.is_none() {
+            // no branch continues here, the enclosing block stops after the if as well
+            core::LLVMBuildUnreachable(env.builder);
+        }
     }
     Ok(ReturnInfo {
         variable: v,
@@ -1,39 +1,249 @@
-use std::path::Path;
+use std::{env, fs};
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::ptr;
 use llvm_sys::{bit_writer, prelude, core};
-use crate::ast::Module;
+use llvm_sys::prelude::LLVMTypeRef;
+use crate::ast::{Expression, Item, Module, Ty, Type};
 use crate::c_str_ptr;
-use crate::error::LithiaError;
-use crate::llvm::LLVMModGenEnv;
+use crate::diagnostics::Diagnostics;
+use crate::error::{LithiaError, LithiaET};
+use crate::lints::LintLevels;
+use crate::llvm::{take_llvm_message, LLVMModGenEnv};
+use crate::llvm::target::Target;
+use crate::test_runner::TestCase;
 
-pub(crate) fn build_llvm_ir(module: Module) -> Result<prelude::LLVMModuleRef, LithiaError>{
-    let mut env = LLVMModGenEnv::new(module.name.0.clone());
-    module.build(&mut env)?;
-    env.finish()
+/// Builds the module, with a C `main` calling the `main` of the module if `entrypoint` is set.
+/// Returns the warnings of the module along with it.
+pub(crate) fn build_llvm_ir(module: Module, target: &Target, entrypoint: bool, debug_info: bool, lints: &LintLevels) -> Result<(prelude::LLVMModuleRef, Diagnostics), Diagnostics>{
+    let mut env = if entrypoint {
+        LLVMModGenEnv::new(module.name.0.clone(), target)
+    } else {
+        LLVMModGenEnv::without_entrypoint(module.name.0.clone(), target)
+    };
+    if debug_info {
+        env.enable_debug_info();
+    }
+    env.set_lint_levels(lints.clone());
+    let mut warnings = match module.build(&mut env) {
+        Ok(warnings) => warnings,
+        Err(e) => return Err(env.discard(e))
+    };
+    let llvm_mod = if entrypoint {
+        env.finish()
+    } else {
+        env.finish_module()
+    };
+    match llvm_mod {
+        Ok(llvm_mod) => Ok((llvm_mod, warnings)),
+        Err(e) => {
+            warnings.push(e);
+            Err(warnings)
+        }
+    }
+}
+
+/// Builds the module with a `main(argc, argv)` entry point running the test at index `argv[1]`,
+/// which exits with the `i32` returned by the test or `0` if it returns nothing.
+pub(crate) fn build_test_harness(module: Module, tests: &[TestCase], target: &Target, debug_info: bool, lints: &LintLevels) -> Result<(prelude::LLVMModuleRef, Diagnostics), Diagnostics>{
+    let mut env = LLVMModGenEnv::without_entrypoint(module.name.0.clone(), target);
+    if debug_info {
+        env.enable_debug_info();
+    }
+    env.set_lint_levels(lints.clone());
+    let warnings;
+    unsafe {
+        let i32_type = core::LLVMInt32Type();
+        let str_type = core::LLVMPointerType(core::LLVMInt8Type(), 0);
+        let mut main_args = [i32_type, core::LLVMPointerType(str_type, 0)];
+        let main_type = core::LLVMFunctionType(i32_type, main_args.as_mut_ptr(), 2, 0);
+        // declared before the module, so a `main` of the module gets renamed instead of the harness
+        let main = core::LLVMAddFunction(env.module, c_str_ptr!("main"), main_type);
+        warnings = match module.build(&mut env) {
+            Ok(warnings) => warnings,
+            Err(e) => return Err(env.discard(e))
+        };
+        let mut atoi_args = [str_type];
+        let atoi_type = core::LLVMFunctionType(i32_type, atoi_args.as_mut_ptr(), 1, 0);
+        let mut atoi = core::LLVMGetNamedFunction(env.module, c_str_ptr!("atoi"));
+        if atoi.is_null() {
+            atoi = core::LLVMAddFunction(env.module, c_str_ptr!("atoi"), atoi_type);
+        }
+        let b = core::LLVMCreateBuilder();
+        let entry = core::LLVMAppendBasicBlock(main, c_str_ptr!("entry"));
+        let select = core::LLVMAppendBasicBlock(main, c_str_ptr!("select"));
+        let unknown = core::LLVMAppendBasicBlock(main, c_str_ptr!("unknown"));
+        core::LLVMPositionBuilderAtEnd(b, entry);
+        let has_arg = core::LLVMBuildICmp(b, llvm_sys::LLVMIntPredicate::LLVMIntSGE, core::LLVMGetParam(main, 0), core::LLVMConstInt(i32_type, 2, 0), c_str_ptr!(""));
+        core::LLVMBuildCondBr(b, has_arg, select, unknown);
+        core::LLVMPositionBuilderAtEnd(b, select);
+        let mut arg_index = [core::LLVMConstInt(core::LLVMInt64Type(), 1, 0)];
+        let arg_ptr = core::LLVMBuildGEP2(b, str_type, core::LLVMGetParam(main, 1), arg_index.as_mut_ptr(), 1, c_str_ptr!(""));
+        let arg = core::LLVMBuildLoad2(b, str_type, arg_ptr, c_str_ptr!(""));
+        let mut atoi_call_args = [arg];
+        let index = core::LLVMBuildCall2(b, atoi_type, atoi, atoi_call_args.as_mut_ptr(), 1, c_str_ptr!("index"));
+        let switch = core::LLVMBuildSwitch(b, index, unknown, tests.len() as u32);
+        for (i, test) in tests.iter().enumerate() {
+            let f = match env.get_item(&test.path, Some(&test.loc)) {
+                Ok(f) => f,
+                Err(e) => {
+                    core::LLVMDisposeBuilder(b);
+                    return Err(env.discard(e).into())
+                }
+            };
+            // checked on the signature, a `u32` is an `i32` as well in LLVM
+            let i32_ty = Type(Ty::Single(vec![], Item::new(&vec!["i32"], test.loc.clone())), test.loc.clone());
+            let returns_i32 = match &f.ast_type.0 {
+                Ty::Signature(args, ret, _, _) if args.is_empty() && ret.0.is_empty() => false,
+                Ty::Signature(args, ret, _, _) if args.is_empty() && **ret == i32_ty => true,
+                _ => {
+                    core::LLVMDisposeBuilder(b);
+                    return Err(env.discard(LithiaET::TagError(format!("test {} has to take no arguments and return () or i32", test.name())).at(test.loc.clone())).into())
+                }
+            };
+            let block = core::LLVMAppendBasicBlock(main, c_str_ptr!("test"));
+            core::LLVMAddCase(switch, core::LLVMConstInt(i32_type, i as u64, 0), block);
+            core::LLVMPositionBuilderAtEnd(b, block);
+            let r = core::LLVMBuildCall2(b, f.llvm_type, f.llvm_value, [].as_mut_ptr(), 0, c_str_ptr!(""));
+            core::LLVMBuildRet(b, if returns_i32 { r } else { core::LLVMConstInt(i32_type, 0, 0) });
+        }
+        core::LLVMPositionBuilderAtEnd(b, unknown);
+        core::LLVMBuildRet(b, core::LLVMConstInt(i32_type, 2, 0));
+        core::LLVMDisposeBuilder(b);
+    }
+    Ok((env.finish_module()?, warnings))
+}
+
+/// Builds `expr` into a new function `name` taking no arguments,
+/// which stores the value of the expression in the global `{name}_result` if it has one.
+pub(crate) fn build_repl_snippet(env: &mut LLVMModGenEnv, expr: &Expression, name: &str) -> Result<Option<(Type, LLVMTypeRef)>, LithiaError>{
+    let function = unsafe {
+        let function_type = core::LLVMFunctionType(core::LLVMVoidType(), [].as_mut_ptr(), 0, 0);
+        core::LLVMAddFunction(env.module, c_str_ptr!(name), function_type)
+    };
+    let outer_f = env.function;
+    let outer_builder = env.builder;
+    env.function = Some(function);
+    env.builder = unsafe {
+        let entry_block = core::LLVMAppendBasicBlock(function, c_str_ptr!("entry"));
+        let b = core::LLVMCreateBuilder();
+        core::LLVMPositionBuilderAtEnd(b, entry_block);
+        b
+    };
+    env.push_stack(true, false);
+    let r = expr.build(env, None).and_then(|r| {
+        if r.return_t.is_some() {
+            return Err(LithiaET::CompilationError("cannot return from a repl expression".to_string()).at(expr.2.clone()))
+        }
+        let out = r.variable.map(|v| unsafe {
+            let global = core::LLVMAddGlobal(env.module, v.llvm_type, c_str_ptr!(format!("{name}_result")));
+            core::LLVMSetInitializer(global, core::LLVMConstNull(v.llvm_type));
+            core::LLVMBuildStore(env.builder, v.llvm_value, global);
+            (v.ast_type, v.llvm_type)
+        });
+        unsafe { core::LLVMBuildRetVoid(env.builder); }
+        Ok(out)
+    });
+    env.pop_stack();
+    unsafe { core::LLVMDisposeBuilder(env.builder); }
+    env.builder = outer_builder;
+    env.function = outer_f;
+    r
+}
+
+pub(crate) fn write_llvm_ir<P: AsRef<Path>>(module: prelude::LLVMModuleRef, ir_file: P) -> Result<(), LithiaError>{
+    let ir_file = ir_file.as_ref().to_string_lossy().to_string();
+    unsafe {
+        let mut err = ptr::null_mut();
+        if core::LLVMPrintModuleToFile(module, c_str_ptr!(ir_file), &mut err) != 0 {
+            return Err(LithiaET::LLVMError(take_llvm_message(err)).error().when(format!("writing {ir_file}")))
+        }
+    }
+    Ok(())
 }
 
-pub(crate) fn build_exe<P: AsRef<Path>>(module: prelude::LLVMModuleRef, llvm_root: P, bitcode_file: P, exe_file: P, dump_ir: bool, disassemble: bool) -> Result<(), LithiaError>{
-    let llvm_root = llvm_root.as_ref().to_string_lossy().to_string();
+pub(crate) fn write_bitcode<P: AsRef<Path>>(module: prelude::LLVMModuleRef, bitcode_file: P) -> Result<(), LithiaError>{
     let bitcode_file = bitcode_file.as_ref().to_string_lossy().to_string();
-    let exe_file = exe_file.as_ref().to_string_lossy().to_string();
-    let success = unsafe { bit_writer::LLVMWriteBitcodeToFile(module, c_str_ptr!(bitcode_file)) };
-    println!("wrote to file with exit code: {success}");
-    if dump_ir {
-        println!();
-        unsafe { core::LLVMDumpModule(module) }
-        println!();
-    }
-    unsafe { core::LLVMDisposeModule(module) }
-    println!("disposed of module");
-    if disassemble {
-        let dis_code = Command::new(format!("{}/bin/llvm-dis.exe", llvm_root))
-            .args([bitcode_file.clone()])
-            .spawn()?.wait()?;
-        println!("disassembled .bc to .ll with {dis_code}");
-    }
-    let compile_code = Command::new(format!("{}/bin/clang.exe", llvm_root))
-        .args([bitcode_file, "-v".to_string(), "-o".to_string(), exe_file])
+    if unsafe { bit_writer::LLVMWriteBitcodeToFile(module, c_str_ptr!(bitcode_file)) } != 0 {
+        return Err(LithiaET::LLVMError(format!("could not write bitcode to {bitcode_file}")).error())
+    }
+    Ok(())
+}
+
+/// Looks for a C compiler driver to link with, `$CC` takes precedence over the ones found on `PATH`.
+fn find_linker() -> Result<PathBuf, LithiaError>{
+    if let Some(cc) = env::var_os("CC") {
+        return Ok(PathBuf::from(cc))
+    }
+    let path = env::var_os("PATH").unwrap_or_default();
+    for name in ["cc", "gcc", "clang"] {
+        for dir in env::split_paths(&path) {
+            let candidate = dir.join(name);
+            if candidate.is_file() {
+                return Ok(candidate)
+            }
+        }
+    }
+    Err(LithiaET::LinkError("could not find a linker, install cc or set the CC environment variable".to_string()).error())
+}
+
+pub(crate) fn link_exe<P: AsRef<Path>>(object_file: P, exe_file: P) -> Result<(), LithiaError>{
+    let linker = find_linker()?;
+    let status = Command::new(&linker)
+        .arg(object_file.as_ref())
+        .arg("-o")
+        .arg(exe_file.as_ref())
+        .spawn()?.wait()?;
+    if !status.success() {
+        return Err(LithiaET::LinkError(format!("{} exited with {status}", linker.display())).error().when("linking executable"))
+    }
+    Ok(())
+}
+
+pub(crate) fn link_shared_lib<P: AsRef<Path>>(object_file: P, lib_file: P) -> Result<(), LithiaError>{
+    let linker = find_linker()?;
+    let status = Command::new(&linker)
+        .arg("-shared")
+        .arg(object_file.as_ref())
+        .arg("-o")
+        .arg(lib_file.as_ref())
+        .spawn()?.wait()?;
+    if !status.success() {
+        return Err(LithiaET::LinkError(format!("{} exited with {status}", linker.display())).error().when("linking shared library"))
+    }
+    Ok(())
+}
+
+/// Looks for an archiver, `$AR` takes precedence over the ones found on `PATH`.
+fn find_archiver() -> Result<PathBuf, LithiaError>{
+    if let Some(ar) = env::var_os("AR") {
+        return Ok(PathBuf::from(ar))
+    }
+    let path = env::var_os("PATH").unwrap_or_default();
+    for name in ["ar", "llvm-ar"] {
+        for dir in env::split_paths(&path) {
+            let candidate = dir.join(name);
+            if candidate.is_file() {
+                return Ok(candidate)
+            }
+        }
+    }
+    Err(LithiaET::LinkError("could not find an archiver, install ar or set the AR environment variable".to_string()).error())
+}
+
+pub(crate) fn archive_static_lib<P: AsRef<Path>>(object_file: P, lib_file: P) -> Result<(), LithiaError>{
+    let archiver = find_archiver()?;
+    // ar adds to an existing archive instead of replacing it
+    if lib_file.as_ref().exists() {
+        fs::remove_file(lib_file.as_ref())?;
+    }
+    let status = Command::new(&archiver)
+        .arg("rcs")
+        .arg(lib_file.as_ref())
+        .arg(object_file.as_ref())
         .spawn()?.wait()?;
-    println!("compiled to binary with {compile_code}");
+    if !status.success() {
+        return Err(LithiaET::LinkError(format!("{} exited with {status}", archiver.display())).error().when("archiving static library"))
+    }
     Ok(())
 }
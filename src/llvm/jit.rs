@@ -0,0 +1,105 @@
+use std::ffi::{c_int, c_uint, c_void, CString};
+use std::{mem, ptr};
+use llvm_sys::{core, execution_engine, prelude, support, target};
+use llvm_sys::execution_engine::LLVMExecutionEngineRef;
+use llvm_sys::target::LLVMTargetDataRef;
+use crate::c_str_ptr;
+use crate::error::{LithiaError, LithiaET};
+use crate::llvm::take_llvm_message;
+
+extern "C" {
+    fn fflush(stream: *mut c_void) -> c_int;
+}
+
+/// MCJIT execution engine owning the module it executes.
+/// Externs are resolved against the symbols of the running process, which includes the host libc.
+pub(crate) struct Jit {
+    engine: LLVMExecutionEngineRef,
+    module: prelude::LLVMModuleRef
+}
+
+impl Jit {
+    pub(crate) fn new(module: prelude::LLVMModuleRef) -> Result<Self, LithiaError> {
+        unsafe {
+            execution_engine::LLVMLinkInMCJIT();
+            if target::LLVM_InitializeNativeTarget() != 0 || target::LLVM_InitializeNativeAsmPrinter() != 0 {
+                core::LLVMDisposeModule(module);
+                return Err(LithiaET::LLVMError("could not initialize native target".to_string()).error().when("creating jit"))
+            }
+            // null loads the symbols of the current process
+            support::LLVMLoadLibraryPermanently(ptr::null());
+            let mut engine = ptr::null_mut();
+            let mut err = ptr::null_mut();
+            if execution_engine::LLVMCreateExecutionEngineForModule(&mut engine, module, &mut err) != 0 {
+                core::LLVMDisposeModule(module);
+                return Err(LithiaET::LLVMError(take_llvm_message(err)).error().when("creating jit"))
+            }
+            Ok(Self {
+                engine,
+                module
+            })
+        }
+    }
+
+    /// Adds another module, its symbols may refer to the ones of all previously added modules.
+    pub(crate) fn add_module(&self, module: prelude::LLVMModuleRef) {
+        unsafe { execution_engine::LLVMAddModule(self.engine, module) }
+    }
+
+    pub(crate) fn target_data(&self) -> LLVMTargetDataRef {
+        unsafe { execution_engine::LLVMGetExecutionEngineTargetData(self.engine) }
+    }
+
+    pub(crate) fn global_address(&self, name: &str) -> Result<u64, LithiaError> {
+        let addr = unsafe { execution_engine::LLVMGetGlobalValueAddress(self.engine, c_str_ptr!(name)) };
+        if addr == 0 {
+            return Err(LithiaET::VariableNotFound(name.to_string()).error().when("looking up jit compiled global"))
+        }
+        Ok(addr)
+    }
+
+    pub(crate) fn function_address(&self, name: &str) -> Result<u64, LithiaError> {
+        let addr = unsafe { execution_engine::LLVMGetFunctionAddress(self.engine, c_str_ptr!(name)) };
+        if addr == 0 {
+            return Err(LithiaET::VariableNotFound(name.to_string()).error().when("looking up jit compiled function"))
+        }
+        Ok(addr)
+    }
+
+    /// Calls a jit compiled function taking no arguments and returning nothing.
+    pub(crate) fn call_void(&self, name: &str) -> Result<(), LithiaError> {
+        let addr = self.function_address(name)?;
+        unsafe {
+            mem::transmute::<u64, extern "C" fn()>(addr)();
+            fflush(ptr::null_mut());
+        }
+        Ok(())
+    }
+
+    /// Calls the generated `main(argc, argv)` entry point with `args` as argv and returns its exit code.
+    pub(crate) fn run_main(&self, args: &[String]) -> Result<i32, LithiaError> {
+        let main = unsafe { core::LLVMGetNamedFunction(self.module, c_str_ptr!("main")) };
+        if main.is_null() {
+            return Err(LithiaET::VariableNotFound("main".to_string()).error().when("looking up jit compiled function"))
+        }
+        let args = args.iter().map(|a| CString::new(a.as_str()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| LithiaET::ArgumentError("program arguments may not contain nul bytes".to_string()).error())?;
+        let mut argv = args.iter().map(|a| a.as_ptr()).collect::<Vec<_>>();
+        argv.push(ptr::null());
+        let envp = [ptr::null()];
+        let code = unsafe {
+            let code = execution_engine::LLVMRunFunctionAsMain(self.engine, main, args.len() as c_uint, argv.as_ptr(), envp.as_ptr());
+            // the jitted code writes through the buffered stdout of libc
+            fflush(ptr::null_mut());
+            code
+        };
+        Ok(code)
+    }
+}
+
+impl Drop for Jit {
+    fn drop(&mut self) {
+        unsafe { execution_engine::LLVMDisposeExecutionEngine(self.engine) }
+    }
+}
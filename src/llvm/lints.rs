@@ -0,0 +1,178 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::mem;
+use crate::ast::{Ident, Statement, Tag, TagValue};
+use crate::diagnostics::Diagnostics;
+use crate::error::{LithiaError, LithiaET};
+use crate::lints::{LevelSource, Lint, LintLevel, LintLevels};
+use crate::llvm::{LLVMModGenEnv, StackEnv};
+use crate::source::span::Span;
+
+/// A variable bound by `let` or as argument, reported by `unused_variables` if it is never read.
+pub(crate) struct Binding {
+    pub(crate) name: String,
+    pub(crate) loc: Span,
+    used: Cell<bool>,
+    /// level of `unused_variables` where the variable was bound
+    level: (LintLevel, LevelSource)
+}
+
+/// Lint levels and the lints reported while building a module.
+#[derive(Default)]
+pub(crate) struct LintState {
+    levels: LintLevels,
+    /// levels set by tags of the functions and expressions being built
+    scopes: Vec<HashMap<Lint, (LintLevel, Span)>>,
+    /// whether an unsafe operation happened inside each `#[unsafe]` expression being built
+    unsafe_uses: Vec<bool>,
+    reported: Diagnostics
+}
+
+impl StackEnv {
+    /// Marks the variable as read.
+    pub(crate) fn mark_used(&self, name: &str) {
+        if let Some(binding) = self.bindings.iter().rev().find(|b| b.name == name) {
+            binding.used.set(true)
+        }
+    }
+}
+
+impl LLVMModGenEnv {
+    pub(crate) fn set_lint_levels(&mut self, levels: LintLevels) {
+        self.lints.levels = levels
+    }
+
+    /// Applies `#[allow(...)]`, `#[warn(...)]` and `#[deny(...)]` tags until `pop_lint_scope`,
+    /// returns whether any of them were given.
+    pub(crate) fn push_lint_scope(&mut self, tags: &HashMap<String, Tag>) -> Result<bool, LithiaError> {
+        let mut scope = HashMap::new();
+        for (name, tag) in tags {
+            let Some(level) = LintLevel::from_tag(name) else { continue };
+            if tag.1.is_empty() {
+                return Err(LithiaET::TagError(format!("#[{name}] expects lint names, e.g. #[{name}(unused)]")).at(tag.2.clone()))
+            }
+            for value in &tag.1 {
+                let TagValue::Ident(Ident(lint, loc)) = value else {
+                    return Err(LithiaET::TagError(format!("#[{name}] expects lint names, e.g. #[{name}(unused)]")).at(tag.2.clone()))
+                };
+                let lints = Lint::from_name(lint)
+                    .ok_or_else(|| LithiaET::TagError(format!("unknown lint {lint}, expected one of {}", Lint::names())).at(loc.clone()))?;
+                for lint in lints {
+                    scope.insert(lint, (level, tag.2.clone()));
+                }
+            }
+        }
+        if scope.is_empty() {
+            return Ok(false)
+        }
+        self.lints.scopes.push(scope);
+        Ok(true)
+    }
+
+    pub(crate) fn pop_lint_scope(&mut self) {
+        self.lints.scopes.pop();
+    }
+
+    /// The level set by the innermost tag, otherwise by the command line or the default of the lint.
+    pub(crate) fn lint_level(&self, lint: Lint) -> (LintLevel, LevelSource) {
+        self.lints.scopes.iter().rev()
+            .find_map(|scope| scope.get(&lint))
+            .map(|(level, loc)| (*level, LevelSource::Tag(loc.clone())))
+            .unwrap_or_else(|| self.lints.levels.get(lint))
+    }
+
+    /// Reports the lint at its current level, `locate` adds the locations to the diagnostic.
+    pub(crate) fn lint<F: FnOnce(LithiaET) -> LithiaError>(&mut self, lint: Lint, message: String, locate: F) {
+        let level = self.lint_level(lint);
+        self.lint_at(lint, level, message, locate)
+    }
+
+    fn lint_at<F: FnOnce(LithiaET) -> LithiaError>(&mut self, lint: Lint, (level, source): (LintLevel, LevelSource), message: String, locate: F) {
+        if level == LintLevel::Allow {
+            return
+        }
+        let error = locate(LithiaET::Lint(lint, level, message));
+        let error = match source {
+            LevelSource::Default => error.note(format!("#[{}({})] is the default", level.name(), lint.name())),
+            LevelSource::CommandLine(flag) => error.note(format!("#[{}({})] is set by {flag}", level.name(), lint.name())),
+            LevelSource::Tag(loc) => error.at_label(loc, "lint level set here")
+        };
+        self.lints.reported.push(error)
+    }
+
+    /// Takes the lints reported so far, denied ones are errors.
+    pub(crate) fn take_lints(&mut self) -> Diagnostics {
+        mem::take(&mut self.lints.reported)
+    }
+
+    /// Forgets the lint scopes of a build that was aborted.
+    pub(crate) fn reset_lints(&mut self) {
+        self.lints.scopes.clear();
+        self.lints.unsafe_uses.clear();
+        self.lints.reported = Diagnostics::new();
+    }
+
+    /// Adds a variable to the innermost stack frame, for `unused_variables` and `shadowed_bindings`.
+    pub(crate) fn bind(&mut self, name: &Ident) {
+        if let Some(earlier) = self.binding_loc(&name.0) {
+            self.lint(Lint::ShadowedBindings, format!("{} shadows an earlier variable", name.0), |e| e
+                .at_label(name.1.clone(), "shadowing variable")
+                .at_label(earlier, "first bound here"));
+        }
+        let level = self.lint_level(Lint::UnusedVariables);
+        self.stack.last_mut().unwrap().bindings.push(Binding {
+            name: name.0.clone(),
+            loc: name.1.clone(),
+            used: Cell::new(name.0.starts_with('_')),
+            level
+        })
+    }
+
+    /// Reports the variables of the innermost stack frame that were never read.
+    pub(crate) fn report_unused_bindings(&mut self) {
+        let bindings = mem::take(&mut self.stack.last_mut().unwrap().bindings);
+        for binding in bindings.into_iter().filter(|b| !b.used.get()) {
+            self.lint_at(Lint::UnusedVariables, binding.level, format!("unused variable {}", binding.name), |e| e
+                .at(binding.loc)
+                .help(format!("prefix it with an underscore if it is unused on purpose: _{}", binding.name)));
+        }
+    }
+
+    /// Enters the unsafe context of an expression tagged `#[unsafe]`, returns false if it already was unsafe.
+    pub(crate) fn enter_unsafe(&mut self, tag: &Tag) -> bool {
+        if self.stack.last().unwrap().unsafe_ctx {
+            self.lint(Lint::UnusedUnsafe, "unnecessary #[unsafe] tag".to_string(), |e| e
+                .at_label(tag.2.clone(), "already in an unsafe context"));
+            return false
+        }
+        self.stack.last_mut().unwrap().unsafe_ctx = true;
+        self.lints.unsafe_uses.push(false);
+        true
+    }
+
+    /// Leaves the unsafe context entered by `enter_unsafe`, `report` is false if building the expression failed.
+    pub(crate) fn exit_unsafe(&mut self, tag: &Tag, report: bool) {
+        self.stack.last_mut().unwrap().unsafe_ctx = false;
+        if !self.lints.unsafe_uses.pop().unwrap_or(true) && report {
+            self.lint(Lint::UnusedUnsafe, "unnecessary #[unsafe] tag".to_string(), |e| e
+                .at_label(tag.2.clone(), "no unsafe operation in the tagged expression"));
+        }
+    }
+
+    /// Called for every unsafe call or cast.
+    pub(crate) fn use_unsafe(&mut self) {
+        if let Some(used) = self.lints.unsafe_uses.last_mut() {
+            *used = true
+        }
+    }
+
+    /// Reports the statements after `diverging`, which are not built.
+    pub(crate) fn lint_unreachable(&mut self, diverging: &Statement, rest: &[Statement]) {
+        let Some(first) = rest.first() else { return };
+        let mut loc = first.2.clone();
+        loc.combine(rest.last().unwrap().2.clone());
+        self.lint(Lint::UnreachableCode, "unreachable statement".to_string(), |e| e
+            .at_label(loc, "unreachable")
+            .at_label(diverging.2.clone(), "any code following this is unreachable"));
+    }
+}
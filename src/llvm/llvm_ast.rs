@@ -1,32 +1,68 @@
 use std::collections::HashMap;
 use std::ffi::{c_uint, c_ulonglong};
-use llvm_sys::{prelude::LLVMBool, prelude, core, LLVMOpcode, LLVMIntPredicate};
-use llvm_sys::prelude::{LLVMTypeRef};
+use llvm_sys::{prelude::LLVMBool, prelude, core, target, LLVMOpcode, LLVMIntPredicate, LLVMTypeKind};
+use llvm_sys::prelude::{LLVMTypeRef, LLVMValueRef};
 use crate::ast::{AstLiteral, Block, Const, Expr, Expression, Func, Ident, Item, Module, Op, Ty, Type};
 use crate::{c_str_ptr};
 use crate::ast::code_printer::CodePrinter;
 use crate::ast::types_impl::TySat;
 use crate::ast::types_impl::TySat::No;
+use crate::diagnostics::Diagnostics;
 use crate::error::{OnParseErr, LithiaError, LithiaET};
+use crate::lints::Lint;
 use crate::llvm::{LLVMModGenEnv, ReturnInfo, Variable};
 use crate::llvm::gen_flow_expressions::compile_if;
-use crate::tokens::{Literal, NumLit};
+use crate::llvm::suggest::{check_tags, suggest, Candidate, EXPRESSION_TAGS, FUNCTION_TAGS, PRIMITIVE_TYPES};
+use crate::tokens::{Literal, NumLit, NumLitTy};
 
 impl Module {
-    pub(crate) fn build(&self, env: &mut LLVMModGenEnv) -> Result<(), LithiaError> {
+    /// Builds all items, an item that fails is reported and the others are still built.
+    /// Returns the warnings of the module if it was built without errors.
+    pub(crate) fn build(&self, env: &mut LLVMModGenEnv) -> Result<Diagnostics, Diagnostics> {
+        let mut diagnostics = Diagnostics::new();
+        // everything is declared before any function body, so modules can refer to each other
+        self.register(env, &mut diagnostics);
+        env.check_imports(&mut diagnostics);
+        // bodies referring to items that could not be declared would only report follow-up errors
+        diagnostics.check()?;
+        self.build_functions(env, &mut diagnostics);
+        diagnostics.append(env.take_lints());
+        diagnostics.check()?;
+        Ok(diagnostics)
+    }
+
+    fn register(&self, env: &mut LLVMModGenEnv, diagnostics: &mut Diagnostics) {
+        env.declare_scope(self);
         // === global consts ===
         for (_ident, constant) in &self.constants {
-            constant.build(env)?;
+            if let Err(e) = constant.build(env) {
+                diagnostics.push(e)
+            }
         }
         // === register functions ===
         for (_ident, func) in &self.functions {
-            func.register(env)?;
+            if let Err(e) = func.register(env) {
+                diagnostics.push(e)
+            }
         }
-        // === build functions ===
+        for (ident, module) in &self.sub_modules {
+            env.enter_module(ident);
+            module.register(env, diagnostics);
+            env.exit_module();
+        }
+    }
+
+    fn build_functions(&self, env: &mut LLVMModGenEnv, diagnostics: &mut Diagnostics) {
         for (_ident, func) in &self.functions {
-            func.build(env)?;
+            if let Err(e) = func.build(env) {
+                diagnostics.push(e)
+            }
+        }
+        for (ident, module) in &self.sub_modules {
+            env.enter_module(ident);
+            module.build_functions(env, diagnostics);
+            env.exit_module();
         }
-        Ok(())
     }
 }
 
@@ -40,7 +76,8 @@ impl Const {
             } else {
                 return Err(LithiaET::CompilationError(format!("constant can only be pointer, found {}", self.print())).at(self.val.2.clone()).when("compiling constant"))
             };
-            let v = core::LLVMAddGlobal(env.module, ty, c_str_ptr!(self.name.0));
+            let path = env.item_path(&self.name.0);
+            let v = core::LLVMAddGlobal(env.module, ty, c_str_ptr!(path));
             let val = if let Expr::Point(box Expression(tags, Expr::Literal(lit), _)) = &self.val.1 {
                 let Variable {
                     ast_type,
@@ -60,7 +97,7 @@ impl Const {
             };
             val.ast_type.satisfies_or_err(&self.ty, TySat::Yes)?;
             core::LLVMSetInitializer(v, val.llvm_value);
-            env.globals.insert(self.name.0.to_string(), Variable {
+            env.globals.insert(path, Variable {
                 ast_type: self.ty.clone(),
                 llvm_type: ty,
                 llvm_value: v,
@@ -73,11 +110,15 @@ impl Const {
 
 impl Func {
     pub(crate) fn register(&self, env: &mut LLVMModGenEnv) -> Result<(), LithiaError> {
+        check_tags(&self.tags, &FUNCTION_TAGS, "functions")?;
         let function_type = unsafe {
             core::LLVMFunctionType(self.ret.llvm_type(env)?, self.args.clone().into_iter().map(|(i, t)|t.llvm_type(env)).collect::<Result<Vec<_>, _>>()?.as_mut_ptr(), self.args.len() as u32, self.tags.contains_key("vararg") as LLVMBool)
         };
-        let function = unsafe { core::LLVMAddFunction(env.module, c_str_ptr!(self.name.0), function_type) };
-        env.globals.insert(self.name.0.to_string(), Variable {
+        let path = env.item_path(&self.name.0);
+        // externs keep their name to link against the C symbol
+        let symbol = if self.tags.contains_key("extern") { &self.name.0 } else { &path };
+        let function = unsafe { core::LLVMAddFunction(env.module, c_str_ptr!(symbol), function_type) };
+        env.globals.insert(path, Variable {
             ast_type: Type(Ty::Signature(self.args.clone().into_iter().map(|(i, t)|t).collect(), Box::new(self.ret.clone()), self.tags.contains_key("unsafe"), self.tags.contains_key("vararg")), self.name.1.clone()),
             llvm_type: function_type,
             llvm_value: function,
@@ -93,11 +134,12 @@ impl Func {
             return if self.tags.contains_key("unsafe") {
                 Ok(())
             } else {
-                Err(LithiaET::UnsafeError("extern function".to_string()).at(self.name.1.clone()))
+                Err(LithiaET::UnsafeError("extern function".to_string()).at(self.name.1.clone())
+                    .help("extern functions have to be tagged with #[unsafe]"))
             }
         }
-        let body = self.body.as_ref().unwrap();
         let function = env.get_var(&self.name.0, Some(&self.loc))?.llvm_value;
+        let lint_scope = env.push_lint_scope(&self.tags)?;
         let outer_f = env.function;
         env.function = Some(function);
         let entry_block = unsafe { core::LLVMAppendBasicBlock(function, c_str_ptr!("entry")) };
@@ -107,6 +149,25 @@ impl Func {
             core::LLVMPositionBuilderAtEnd(b, entry_block);
             b
         };
+        let stack_depth = env.stack.len();
+        let r = self.build_body(env, function);
+        // a failed body leaves its scopes behind, the following functions are still built
+        env.stack.truncate(stack_depth);
+        env.end_debug_function();
+        unsafe {
+            core::LLVMDisposeBuilder(env.builder);
+        }
+        env.builder = entry_builder;
+        env.function = outer_f;
+        if lint_scope {
+            env.pop_lint_scope();
+        }
+        r
+    }
+
+    fn build_body(&self, env: &mut LLVMModGenEnv, function: LLVMValueRef) -> Result<(), LithiaError> {
+        let body = self.body.as_ref().unwrap();
+        env.begin_debug_function(self, function)?;
         env.push_stack(true, self.tags.contains_key("unsafe"));
         self.args.iter()
             .map(|(ident, ty)|(ident, ty, ty.llvm_type(env)))
@@ -114,6 +175,7 @@ impl Func {
             .into_iter()
             .enumerate()
             .map(|(i, (ident, ty, llvm_ty))| {
+                env.bind(ident);
                 let _ = env.stack.last_mut().unwrap().vars.insert(ident.0.clone(),
                                                                Variable {
                                                                    ast_type: ty.clone(),
@@ -126,6 +188,8 @@ impl Func {
             .collect::<Result<Vec<()>, LithiaError>>()?;
         let r = body.build(env, None)?;
         r.variable.as_ref().map(|v| unsafe { core::LLVMBuildRet(env.builder, v.llvm_value) });
+        // a return in only some branches still lets the body end without one
+        let falls_through = r.variable.is_none() && unsafe { core::LLVMGetBasicBlockTerminator(core::LLVMGetInsertBlock(env.builder)).is_null() };
         let v = match (r.variable, r.return_t) {
             (None, None) => None,
             (None, Some(rt)) => Some(rt),
@@ -133,7 +197,8 @@ impl Func {
             (Some(v), Some(rt)) => {
                 if v.ast_type != rt.0 {
                     return Err(LithiaET::TypeError(v.ast_type.clone(), rt.0.clone())
-                        .ats(vec![v.ast_type.1.clone(), rt.0.1.clone()]))
+                        .at_label(rt.0.1.clone(), format!("returns {} here", rt.0.print()))
+                        .at_label(v.ast_type.1.clone(), format!("resolves to {} here", v.ast_type.print())))
                 }
                 Some(rt)
             }
@@ -141,294 +206,339 @@ impl Func {
         env.pop_stack();
         if let Some(r) = &v {
             r.0.satisfies_or_err(&self.ret, TySat::Yes).e_at_add(r.0.1.clone())?;
+            if falls_through {
+                if !self.ret.0.is_empty() {
+                    return Err(LithiaET::CompilationError("missing return".to_string())
+                        .at_label(self.name.1.clone(), format!("{} can end without returning a value", self.name.0))
+                        .at_label(self.ret.1.clone(), format!("expected {} because of the return type", self.ret.print())))
+                }
+                unsafe { core::LLVMBuildRetVoid(env.builder); }
+            }
         } else {
             if !self.ret.0.is_empty() {
                 return Err(LithiaET::CompilationError(format!("function returns {} but got empty type", self.ret.print())).at(self.ret.1.clone()))
             }
             unsafe { core::LLVMBuildRetVoid(env.builder); }
         }
-        unsafe {
-            core::LLVMDisposeBuilder(env.builder);
-        }
-        env.builder = entry_builder;
-        env.function = outer_f;
         Ok(())
     }
 }
 
 impl Expression {
     pub(crate) fn build(&self, env: &mut LLVMModGenEnv, ret_name: Option<String>) -> Result<ReturnInfo, LithiaError> {
-        let outer_unsafe = env.stack.last().unwrap().unsafe_ctx;
-        if self.0.contains_key("unsafe") {
-            env.stack.last_mut().unwrap().unsafe_ctx = true;
+        check_tags(&self.0, &EXPRESSION_TAGS, "expressions")?;
+        let lint_scope = env.push_lint_scope(&self.0)?;
+        let unsafe_tag = self.0.get("unsafe");
+        let entered_unsafe = unsafe_tag.map(|tag| env.enter_unsafe(tag)).unwrap_or(false);
+        let outer_loc = env.set_debug_loc(&self.2);
+        let r = self.build_expr(env, ret_name);
+        env.restore_debug_loc(outer_loc);
+        if entered_unsafe {
+            env.exit_unsafe(unsafe_tag.unwrap(), r.is_ok());
         }
-        let r =
-            Ok(match &self.1 {
-                Expr::Expr(box expr) => expr.build(env, ret_name)?,
-                Expr::Literal(lit) => {
-                    let v = lit.llvm_literal(env)?;
-                    ReturnInfo {
-                        variable: Some(v),
-                        return_t: None,
-                        loc: self.2.clone()
-                    }
-                },
-                Expr::Return(e) => {
-                    let rt = e.as_ref().map(|e|e.build(env, None))
-                        .map(|r| r.map(|r| r.variable))
-                        .map_or(Ok(None), |v| v.map(Some))
-                        .map(|x| x.flatten().map(|v| {
-                            unsafe { core::LLVMBuildRet(env.builder, v.llvm_value); }
-                            Ok((v.ast_type, v.llvm_type))
-                        }))?
-                        .unwrap_or_else(|| {
-                            unsafe { core::LLVMBuildRetVoid(env.builder); }
-                            let t = Type(Ty::Tuple(vec![]), self.2.clone());
-                            t.llvm_type(env).map(|lt| (t, lt))
-                        })?;
-                    ReturnInfo {
-                        variable: None,
-                        return_t: Some(rt),
-                        loc: self.2.clone()
-                    }
+        if lint_scope {
+            env.pop_lint_scope();
+        }
+        r
+    }
+
+    fn build_expr(&self, env: &mut LLVMModGenEnv, ret_name: Option<String>) -> Result<ReturnInfo, LithiaError> {
+        Ok(match &self.1 {
+            Expr::Expr(box expr) => expr.build(env, ret_name)?,
+            Expr::Literal(lit) => {
+                let v = lit.llvm_literal(env)?;
+                ReturnInfo {
+                    variable: Some(v),
+                    return_t: None,
+                    loc: self.2.clone()
                 }
-                Expr::Point(expr) => {
-                    let r = expr.build(env, None)?;
-                    let v = r.resolve_var()?;
-                    let ptr =  unsafe {
-                        let ptr = core::LLVMBuildAlloca(env.builder, v.llvm_type, c_str_ptr!(ret_name.unwrap_or(String::new())));
-                        core::LLVMBuildStore(env.builder, v.llvm_value, ptr);
-                        ptr
-                    };
-                    ReturnInfo {
-                        variable: Some(Variable{
-                            ast_type: Type(Ty::Pointer(Box::new(v.ast_type)), self.2.clone()),
-                            llvm_type:  unsafe { core::LLVMPointerType(v.llvm_type, 0) } , // TODO: replace 0
-                            llvm_value: ptr,
-                            mutable: false
-                        }),
-                        return_t: r.return_t,
-                        loc: self.2.clone()
-                    }
-                },
-                Expr::Deref(expr) => {
-                    let r = expr.build(env, None)?;
-                    let v = r.resolve_var()?;
-                    if let Ty::RawPointer = &v.ast_type.0 {
-                        return Err(LithiaET::TypeError(Type(Ty::Pointer(Box::new(Type::placeholder(self.2.clone()))), self.2.clone()), v.ast_type).at(self.2.clone()).when("compiling deref"))
-                    }
-                    let inner_ty = if let Ty::Pointer(box ty) = &v.ast_type.0 { ty } else {
-                        return Err(LithiaET::TypeError(Type(Ty::Pointer(Box::new(Type::placeholder(self.2.clone()))), self.2.clone()), v.ast_type).at(self.2.clone()).when("compiling deref"))
-                    };
-                    let llvm_ty = inner_ty.llvm_type(env)?;
-                    let deref =  unsafe { core::LLVMBuildLoad2(env.builder, llvm_ty, v.llvm_value, c_str_ptr!(ret_name.unwrap_or(String::new()))) };
-                    ReturnInfo {
-                        variable: Some(Variable {
-                            ast_type: inner_ty.clone(),
-                            llvm_type: llvm_ty,
-                            llvm_value: deref,
-                            mutable: false
-                        }),
-                        return_t: r.return_t,
-                        loc: self.2.clone()
-                    }
+            },
+            Expr::Return(e) => {
+                let rt = e.as_ref().map(|e|e.build(env, None))
+                    .map(|r| r.map(|r| r.variable))
+                    .map_or(Ok(None), |v| v.map(Some))
+                    .map(|x| x.flatten().map(|v| {
+                        unsafe { core::LLVMBuildRet(env.builder, v.llvm_value); }
+                        Ok((v.ast_type, v.llvm_type))
+                    }))?
+                    .unwrap_or_else(|| {
+                        unsafe { core::LLVMBuildRetVoid(env.builder); }
+                        let t = Type(Ty::Tuple(vec![]), self.2.clone());
+                        t.llvm_type(env).map(|lt| (t, lt))
+                    })?;
+                ReturnInfo {
+                    variable: None,
+                    return_t: Some(rt),
+                    loc: self.2.clone()
+                }
+            }
+            Expr::Point(expr) => {
+                let r = expr.build(env, None)?;
+                let v = r.resolve_var()?;
+                let ptr =  unsafe {
+                    let ptr = core::LLVMBuildAlloca(env.builder, v.llvm_type, c_str_ptr!(ret_name.unwrap_or(String::new())));
+                    core::LLVMBuildStore(env.builder, v.llvm_value, ptr);
+                    ptr
+                };
+                ReturnInfo {
+                    variable: Some(Variable{
+                        ast_type: Type(Ty::Pointer(Box::new(v.ast_type)), self.2.clone()),
+                        llvm_type:  unsafe { core::LLVMPointerType(v.llvm_type, 0) } , // TODO: replace 0
+                        llvm_value: ptr,
+                        mutable: false
+                    }),
+                    return_t: r.return_t,
+                    loc: self.2.clone()
+                }
+            },
+            Expr::Deref(expr) => {
+                let r = expr.build(env, None)?;
+                let v = r.resolve_var()?;
+                if let Ty::RawPointer = &v.ast_type.0 {
+                    return Err(LithiaET::TypeError(Type(Ty::Pointer(Box::new(Type::placeholder(self.2.clone()))), self.2.clone()), v.ast_type).at(self.2.clone()).when("compiling deref"))
                 }
-                Expr::Variable(var) => {
-                    let mut var = env.get_var(&var.0, Some(&var.1))?;
-                    if var.mutable {
-                        var.llvm_value = unsafe { core::LLVMBuildLoad2(env.builder, var.llvm_type, var.llvm_value, c_str_ptr!(ret_name.unwrap_or(String::new()))) };
+                let inner_ty = if let Ty::Pointer(box ty) = &v.ast_type.0 { ty } else {
+                    return Err(LithiaET::TypeError(Type(Ty::Pointer(Box::new(Type::placeholder(self.2.clone()))), self.2.clone()), v.ast_type).at(self.2.clone()).when("compiling deref"))
+                };
+                let llvm_ty = inner_ty.llvm_type(env)?;
+                let deref =  unsafe { core::LLVMBuildLoad2(env.builder, llvm_ty, v.llvm_value, c_str_ptr!(ret_name.unwrap_or(String::new()))) };
+                ReturnInfo {
+                    variable: Some(Variable {
+                        ast_type: inner_ty.clone(),
+                        llvm_type: llvm_ty,
+                        llvm_value: deref,
+                        mutable: false
+                    }),
+                    return_t: r.return_t,
+                    loc: self.2.clone()
+                }
+            }
+            Expr::Variable(var) => {
+                let mut var = env.get_var(&var.0, Some(&var.1))?;
+                if var.mutable {
+                    var.llvm_value = unsafe { core::LLVMBuildLoad2(env.builder, var.llvm_type, var.llvm_value, c_str_ptr!(ret_name.unwrap_or(String::new()))) };
+                }
+                var.mutable = false;
+                ReturnInfo {
+                    variable: Some(var),
+                    return_t: None,
+                    loc: self.2.clone()
+                }
+            },
+            Expr::Block(block) => block.build(env, ret_name)?,
+            Expr::FuncCall(fun, args) => {
+                let var = if fun.0.len() == 1 {
+                    env.get_var(&fun.0[0].0, Some(&fun.1))?
+                } else {
+                    env.get_item(&fun.0.iter().map(|i| i.0.clone()).collect::<Vec<_>>(), Some(&fun.1))?
+                };
+                if let Ty::Signature(arg_types, ret, is_unsafe, vararg) = var.ast_type.0 {
+                    if is_unsafe && !env.stack.last().unwrap().unsafe_ctx {
+                        return Err(LithiaET::UnsafeError("unsafe function".to_string())
+                            .at_label(fun.1.clone(), "called here")
+                            .at_label(var.ast_type.1.clone(), "declared unsafe here")
+                            .help("tag the call or the calling function with #[unsafe]"))
                     }
-                    var.mutable = false;
-                    ReturnInfo {
-                        variable: Some(var),
-                        return_t: None,
-                        loc: self.2.clone()
+                    if is_unsafe {
+                        env.use_unsafe();
                     }
-                },
-                Expr::Block(block) => block.build(env, ret_name)?,
-                Expr::FuncCall(fun, args) => {
-                    let var = env.get_var(&fun.0.first().unwrap().0, Some(&fun.1))?;
-                    if let Ty::Signature(arg_types, ret, is_unsafe, vararg) = var.ast_type.0 {
-                        if is_unsafe && !env.stack.last().unwrap().unsafe_ctx {
-                            return Err(LithiaET::UnsafeError("unsafe function".to_string()).ats(vec![var.ast_type.1.clone(), fun.1.clone()]))
+                    if arg_types.len() != args.len() && (arg_types.len() > args.len() || !vararg) {
+                        return if vararg {
+                            Err(LithiaET::CompilationError(format!("expected {} args or more, got {}", arg_types.len(), args.len())).at(self.2.clone()).when("compiling function call"))
+                        } else {
+                            Err(LithiaET::CompilationError(format!("expected {} args, got {}", arg_types.len(), args.len())).at(self.2.clone()).when("compiling function call"))
                         }
-                        if arg_types.len() != args.len() && (arg_types.len() > args.len() || !vararg) {
-                            return if vararg {
-                                Err(LithiaET::CompilationError(format!("expected {} args or more, got {}", arg_types.len(), args.len())).at(self.2.clone()).when("compiling function call"))
-                            } else {
-                                Err(LithiaET::CompilationError(format!("expected {} args, got {}", arg_types.len(), args.len())).at(self.2.clone()).when("compiling function call"))
+                    }
+                    let mut ret_t: Option<(Type, LLVMTypeRef)> = None;
+                    let mut llvm_args = args.iter().zip(arg_types)
+                        .map(|(expr, t)| expr.build(env, None).map(|r| {
+                            let v = r.resolve_var()?;
+                            if let Some(rt) = &r.return_t {
+                                if let Some(rtt) = &ret_t {
+                                    rt.0.satisfies_or_err(&rtt.0, TySat::Yes)?;
+                                } else { ret_t = r.return_t.clone() }
                             }
-                        }
-                        let mut ret_t: Option<(Type, LLVMTypeRef)> = None;
-                        let mut llvm_args = args.iter().zip(arg_types)
-                            .map(|(expr, t)| expr.build(env, None).map(|r| {
+                            v.ast_type.satisfies_or_err(&t, TySat::Yes).e_at_add(expr.2.clone())?;
+                            let param_type = t.llvm_type(env)?;
+                            // implicit pointer casts, e.g. &array to &slice, keep the llvm type of the argument
+                            Ok(unsafe {
+                                let value_type = core::LLVMTypeOf(v.llvm_value);
+                                if value_type != param_type && core::LLVMGetTypeKind(value_type) == LLVMTypeKind::LLVMPointerTypeKind {
+                                    core::LLVMBuildPointerCast(env.builder, v.llvm_value, param_type, c_str_ptr!(""))
+                                } else {
+                                    v.llvm_value
+                                }
+                            })
+                        }).flatten())
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if llvm_args.len() < args.len() {
+                        llvm_args.append(&mut args.into_iter().skip(llvm_args.len())
+                            .map(|expr| expr.build(env, None).map(|r|{
                                 let v = r.resolve_var()?;
                                 if let Some(rt) = &r.return_t {
                                     if let Some(rtt) = &ret_t {
                                         rt.0.satisfies_or_err(&rtt.0, TySat::Yes)?;
                                     } else { ret_t = r.return_t.clone() }
                                 }
-                                v.ast_type.satisfies_or_err(&t, TySat::Yes).e_at_add(expr.2.clone())?;
                                 Ok(v.llvm_value)
                             }).flatten())
-                            .collect::<Result<Vec<_>, _>>()?;
-                        if llvm_args.len() < args.len() {
-                            llvm_args.append(&mut args.into_iter().skip(llvm_args.len())
-                                .map(|expr| expr.build(env, None).map(|r|{
-                                    let v = r.resolve_var()?;
-                                    if let Some(rt) = &r.return_t {
-                                        if let Some(rtt) = &ret_t {
-                                            rt.0.satisfies_or_err(&rtt.0, TySat::Yes)?;
-                                        } else { ret_t = r.return_t.clone() }
-                                    }
-                                    Ok(v.llvm_value)
-                                }).flatten())
-                                .collect::<Result<Vec<_>, _>>()?)
-                        }
-                        let ty = ret.llvm_type(env)?;
-                        let out =  unsafe { core::LLVMBuildCall2(env.builder, var.llvm_type, var.llvm_value, llvm_args.as_mut_ptr(), args.len() as c_uint, c_str_ptr!(ret_name.unwrap_or(String::new()))) };
-
-                        ReturnInfo {
-                            variable: Some(Variable {
-                                ast_type: *ret,
-                                llvm_type: ty,
-                                llvm_value: out,
-                                mutable: false
-                            }),
-                            return_t: ret_t,
-                            loc: self.2.clone()
-                        }
-                    } else {
-                        return Err(LithiaET::TypeError(Type(Ty::Signature(vec![], Box::new(Type::placeholder(self.2.clone())), false, false), self.2.clone()), var.ast_type).at(self.2.clone()).when("compiling expression"))
+                            .collect::<Result<Vec<_>, _>>()?)
                     }
-                },
-                Expr::VarCreate(name, mutable, ty, expr) => {
-                    let mut r = expr.build(env, Some(name.0.clone()))?;
-                    let mut v = r.resolve_var()?;
-                    v.mutable = *mutable;
-                    if v.mutable {
-                        unsafe {
-                            let ptr = core::LLVMBuildAlloca(env.builder, v.llvm_type, c_str_ptr!(""));
-                            core::LLVMBuildStore(env.builder, v.llvm_value, ptr);
-                            v.llvm_value = ptr;
-                        }
-                    }
-                    if let Some(t) = &ty {
-                        v.ast_type.satisfies_or_err(t, TySat::Yes)?;
+                    let ty = ret.llvm_type(env)?;
+                    let out =  unsafe { core::LLVMBuildCall2(env.builder, var.llvm_type, var.llvm_value, llvm_args.as_mut_ptr(), args.len() as c_uint, c_str_ptr!(ret_name.unwrap_or(String::new()))) };
+
+                    ReturnInfo {
+                        variable: Some(Variable {
+                            ast_type: *ret,
+                            llvm_type: ty,
+                            llvm_value: out,
+                            mutable: false
+                        }),
+                        return_t: ret_t,
+                        loc: self.2.clone()
                     }
-                    env.stack.last_mut().unwrap().vars.insert(name.0.clone(), v.clone());
-                    r.variable = None; // var creation doesnt resolve to variable
-                    r
+                } else {
+                    return Err(LithiaET::TypeError(Type(Ty::Signature(vec![], Box::new(Type::placeholder(self.2.clone())), false, false), self.2.clone()), var.ast_type).at(self.2.clone()).when("compiling expression"))
                 }
-                Expr::VarAssign(name, op, expr) => {
-                    let mut expr = expr.clone();
-                    let var = env.get_var(&name.0, Some(&self.2))?;
-                    if let Some(op) = op {
-                        expr = Box::new(Expression(HashMap::new(), Expr::BinaryOp(
-                            op.clone(),
-                            Box::new(Expression(HashMap::new(), Expr::Variable(name.clone()), op.1.clone())),
-                            expr
-                        ), op.1.clone()))
-                    }
-                    let mut r = expr.build(env, None)?;
-                    if !var.mutable {
-                        return Err(LithiaET::CompilationError(format!("cant assign to immutable variable")).at(self.2.clone()))
+            },
+            Expr::VarCreate(name, mutable, ty, expr) => {
+                let mut r = expr.build(env, Some(name.0.clone()))?;
+                let mut v = r.resolve_var()?;
+                v.mutable = *mutable;
+                if v.mutable {
+                    unsafe {
+                        let ptr = core::LLVMBuildAlloca(env.builder, v.llvm_type, c_str_ptr!(""));
+                        core::LLVMBuildStore(env.builder, v.llvm_value, ptr);
+                        v.llvm_value = ptr;
                     }
-                    let mut v = r.resolve_var()?;
-                    v.ast_type.satisfies_or_err(&var.ast_type, TySat::Yes)?;
-                    unsafe { core::LLVMBuildStore(env.builder, v.llvm_value, var.llvm_value); }
-                    r.variable = None;
-                    r
                 }
-                Expr::Cast(expr, target_t) => {
-                    let r = expr.build(env, None)?;
-                    let v = r.resolve_var()?;
-                    let sat = v.ast_type.satisfies(target_t);
-                    if sat != TySat::Cast && sat != TySat::CastUnsafe {
-                        return Err(LithiaET::CastError(v.ast_type, target_t.clone()).at(self.2.clone()))
-                    }
-                    if sat == TySat::CastUnsafe && !env.stack.last().unwrap().unsafe_ctx {
-                        return Err(LithiaET::UnsafeError("unsafe cast".to_string()).at(self.2.clone()))
-                    }
-                    let llvm_type = target_t.llvm_type(env)?;
-                    let op_code =  unsafe { core::LLVMGetCastOpcode(v.llvm_value, false as LLVMBool, llvm_type, false as LLVMBool) };
+                if let Some(t) = &ty {
+                    // an unknown type name is reported instead of the mismatch
+                    t.llvm_type(env)?;
+                    v.ast_type.satisfies_or_err(t, TySat::Yes)?;
+                }
+                env.debug_variable(name, &v)?;
+                env.bind(name);
+                env.stack.last_mut().unwrap().vars.insert(name.0.clone(), v.clone());
+                r.variable = None; // var creation doesnt resolve to variable
+                r
+            }
+            Expr::VarAssign(name, op, expr) => {
+                let mut expr = expr.clone();
+                // only `op=` reads the variable, through the expression it is rewritten to
+                let var = env.get_assigned_var(&name.0, Some(&self.2))?;
+                if let Some(op) = op {
+                    expr = Box::new(Expression(HashMap::new(), Expr::BinaryOp(
+                        op.clone(),
+                        Box::new(Expression(HashMap::new(), Expr::Variable(name.clone()), op.1.clone())),
+                        expr
+                    ), op.1.clone()))
+                }
+                let mut r = expr.build(env, None)?;
+                if !var.mutable {
+                    let error = LithiaET::CompilationError(format!("cant assign to immutable variable")).at(self.2.clone());
+                    return Err(match env.binding_loc(&name.0) {
+                        Some(loc) => error
+                            .at_label(loc, format!("{} is bound immutably here", name.0))
+                            .help(format!("make it mutable: let mut {}", name.0)),
+                        None => error
+                    })
+                }
+                let mut v = r.resolve_var()?;
+                v.ast_type.satisfies_or_err(&var.ast_type, TySat::Yes)?;
+                unsafe { core::LLVMBuildStore(env.builder, v.llvm_value, var.llvm_value); }
+                r.variable = None;
+                r
+            }
+            Expr::Cast(expr, target_t) => {
+                let r = expr.build(env, None)?;
+                let v = r.resolve_var()?;
+                let sat = v.ast_type.satisfies(target_t);
+                if sat != TySat::Cast && sat != TySat::CastUnsafe {
+                    return Err(LithiaET::CastError(v.ast_type, target_t.clone()).at(self.2.clone()))
+                }
+                if sat == TySat::CastUnsafe && !env.stack.last().unwrap().unsafe_ctx {
+                    return Err(LithiaET::UnsafeError("unsafe cast".to_string()).at(self.2.clone())
+                        .help("tag the cast or the surrounding function with #[unsafe]"))
+                }
+                if sat == TySat::CastUnsafe {
+                    env.use_unsafe();
+                }
+                let llvm_type = target_t.llvm_type(env)?;
+                let op_code =  unsafe { core::LLVMGetCastOpcode(v.llvm_value, false as LLVMBool, llvm_type, false as LLVMBool) };
+                ReturnInfo {
+                    variable: Some(Variable {
+                        ast_type: target_t.clone(),
+                        llvm_type,
+                        llvm_value: unsafe { core::LLVMBuildCast(env.builder, op_code, v.llvm_value, llvm_type, c_str_ptr!(ret_name.unwrap_or(String::new()))) },
+                        mutable: false
+                    }),
+                    return_t: r.return_t,
+                    loc: self.2.clone()
+                }
+            }
+            Expr::BinaryOp(op, a, b) => {
+                let ra = a.build(env, None)?;
+                let rb = b.build(env, None)?;
+                let va = ra.resolve_var()?;
+                let vb = rb.resolve_var()?;
+                let opc = match &op.0 {
+                    Op::Add => Some(LLVMOpcode::LLVMAdd),
+                    Op::Sub => Some(LLVMOpcode::LLVMSub),
+                    Op::Mul => Some(LLVMOpcode::LLVMMul),
+                    Op::Div => Some(LLVMOpcode::LLVMUDiv),
+                    Op::Or => Some(LLVMOpcode::LLVMOr),
+                    Op::And => Some(LLVMOpcode::LLVMAnd),
+                    Op::BinOr => Some(LLVMOpcode::LLVMOr),
+                    Op::BinAnd => Some(LLVMOpcode::LLVMAnd),
+                    Op::LShift => Some(LLVMOpcode::LLVMShl),
+                    Op::RShift => Some(LLVMOpcode::LLVMAShr), // A stands for Arithmetic and L stands for logical, see: https://stackoverflow.com/questions/141525/what-are-bitwise-shift-bit-shift-operators-and-how-do-they-work
+                    Op::LT => None,
+                    Op::LE => None,
+                    Op::GT => None,
+                    Op::GE => None,
+                    Op::EQ => None,
+                    Op::NE => None,
+                    invalid => panic!("didnt expect op {invalid:?}")
+                };
+                if let Some(op) = opc {
+                    let r =  unsafe { core::LLVMBuildBinOp(env.builder, op, va.llvm_value, vb.llvm_value, c_str_ptr!(ret_name.unwrap_or(String::new()))) };
                     ReturnInfo {
                         variable: Some(Variable {
-                            ast_type: target_t.clone(),
-                            llvm_type,
-                            llvm_value: unsafe { core::LLVMBuildCast(env.builder, op_code, v.llvm_value, llvm_type, c_str_ptr!(ret_name.unwrap_or(String::new()))) },
+                            ast_type: va.ast_type,
+                            llvm_type: va.llvm_type,
+                            llvm_value: r,
                             mutable: false
                         }),
-                        return_t: r.return_t,
+                        return_t: ra.return_t,
                         loc: self.2.clone()
                     }
-                }
-                Expr::BinaryOp(op, a, b) => {
-                    let ra = a.build(env, None)?;
-                    let rb = b.build(env, None)?;
-                    let va = ra.resolve_var()?;
-                    let vb = rb.resolve_var()?;
-                    let opc = match &op.0 {
-                        Op::Add => Some(LLVMOpcode::LLVMAdd),
-                        Op::Sub => Some(LLVMOpcode::LLVMSub),
-                        Op::Mul => Some(LLVMOpcode::LLVMMul),
-                        Op::Div => Some(LLVMOpcode::LLVMUDiv),
-                        Op::Or => Some(LLVMOpcode::LLVMOr),
-                        Op::And => Some(LLVMOpcode::LLVMAnd),
-                        Op::BinOr => Some(LLVMOpcode::LLVMOr),
-                        Op::BinAnd => Some(LLVMOpcode::LLVMAnd),
-                        Op::LShift => Some(LLVMOpcode::LLVMShl),
-                        Op::RShift => Some(LLVMOpcode::LLVMAShr), // A stands for Arithmetic and L stands for logical, see: https://stackoverflow.com/questions/141525/what-are-bitwise-shift-bit-shift-operators-and-how-do-they-work
-                        Op::LT => None,
-                        Op::LE => None,
-                        Op::GT => None,
-                        Op::GE => None,
-                        Op::EQ => None,
-                        Op::NE => None,
+                } else {
+                    let r =  unsafe { core::LLVMBuildICmp(env.builder, match &op.0 {
+                        Op::LT => LLVMIntPredicate::LLVMIntSLT,
+                        Op::LE => LLVMIntPredicate::LLVMIntSLE,
+                        Op::GT => LLVMIntPredicate::LLVMIntSGT,
+                        Op::GE => LLVMIntPredicate::LLVMIntSGE,
+                        Op::EQ => LLVMIntPredicate::LLVMIntEQ,
+                        Op::NE => LLVMIntPredicate::LLVMIntNE,
                         invalid => panic!("didnt expect op {invalid:?}")
-                    };
-                    if let Some(op) = opc {
-                        let r =  unsafe { core::LLVMBuildBinOp(env.builder, op, va.llvm_value, vb.llvm_value, c_str_ptr!(ret_name.unwrap_or(String::new()))) };
-                        ReturnInfo {
-                            variable: Some(Variable {
-                                ast_type: va.ast_type,
-                                llvm_type: va.llvm_type,
-                                llvm_value: r,
-                                mutable: false
-                            }),
-                            return_t: ra.return_t,
-                            loc: self.2.clone()
-                        }
-                    } else {
-                        let r =  unsafe { core::LLVMBuildICmp(env.builder, match &op.0 {
-                            Op::LT => LLVMIntPredicate::LLVMIntSLT,
-                            Op::LE => LLVMIntPredicate::LLVMIntSLE,
-                            Op::GT => LLVMIntPredicate::LLVMIntSGT,
-                            Op::GE => LLVMIntPredicate::LLVMIntSGE,
-                            Op::EQ => LLVMIntPredicate::LLVMIntEQ,
-                            Op::NE => LLVMIntPredicate::LLVMIntNE,
-                            invalid => panic!("didnt expect op {invalid:?}")
-                        }, va.llvm_value, vb.llvm_value, c_str_ptr!(ret_name.unwrap_or(String::new()))) };
-                        let loc = op.1.clone();
-                        ReturnInfo {
-                            variable: Some(Variable {
-                                ast_type: Type(Ty::Single(vec![], Item::new(&vec!["bool"], loc.clone())), loc.clone()),
-                                llvm_type: unsafe { core::LLVMInt1Type() },
-                                llvm_value: r,
-                                mutable: false
-                            }),
-                            return_t: ra.return_t,
-                            loc: self.2.clone()
-                        }
+                    }, va.llvm_value, vb.llvm_value, c_str_ptr!(ret_name.unwrap_or(String::new()))) };
+                    let loc = op.1.clone();
+                    ReturnInfo {
+                        variable: Some(Variable {
+                            ast_type: Type(Ty::Single(vec![], Item::new(&vec!["bool"], loc.clone())), loc.clone()),
+                            llvm_type: unsafe { core::LLVMInt1Type() },
+                            llvm_value: r,
+                            mutable: false
+                        }),
+                        return_t: ra.return_t,
+                        loc: self.2.clone()
                     }
                 }
-                Expr::If(expr, body, else_body) => compile_if(expr, body, else_body, env, ret_name)?,
-                _ => unimplemented!()
-            });
-        if self.0.contains_key("unsafe") {
-            env.stack.last_mut().unwrap().unsafe_ctx = outer_unsafe;
-        }
-        r
+            }
+            Expr::If(expr, body, else_body) => compile_if(expr, body, else_body, env, ret_name)?,
+            _ => unimplemented!()
+        })
     }
 }
 
@@ -437,11 +547,26 @@ impl Block {
         let mut ret_t: Option<(Type, LLVMTypeRef)> = None;
         for (i, stmt) in self.0.iter().enumerate() {
             let r = stmt.0.build(env, ret_name.clone())?;
+            if let (true, Expr::FuncCall(fun, _), Some(v)) = (stmt.1, &stmt.0.1, &r.variable) {
+                if !v.ast_type.0.is_empty() {
+                    // the statement without its tags
+                    let mut loc = stmt.2.clone();
+                    loc.start = fun.1.start;
+                    let lint_scope = env.push_lint_scope(&stmt.0.0)?;
+                    env.lint(Lint::UnusedResults, format!("unused result of type {}", v.ast_type.print()), |e| e
+                        .at(loc)
+                        .help("assign it to `_` if it is unused on purpose: let _ = ..."));
+                    if lint_scope {
+                        env.pop_lint_scope();
+                    }
+                }
+            }
             if let Some(rt) = &r.return_t {
                 if let Some(rtt) = &ret_t {
                     rt.0.satisfies_or_err(&rtt.0, TySat::Yes)?;
                 } else { ret_t = r.return_t.clone() }
                 if r.variable.is_none() {
+                    env.lint_unreachable(stmt, &self.0[i + 1..]);
                     return Ok(ReturnInfo {
                         variable: None,
                         return_t: r.return_t,
@@ -450,6 +575,7 @@ impl Block {
                 }
             }
             if let Expr::Return(_) = stmt.0.1 {
+                env.lint_unreachable(stmt, &self.0[i + 1..]);
                 return Ok(ReturnInfo {
                     variable: None,
                     return_t: r.return_t,
@@ -487,25 +613,25 @@ impl Type {
         unsafe {
             Ok(match &self.0 {
                 Ty::Single(generics, base_type) => {
-                    if generics.len() > 0 || base_type.0.len() > 1 {
-                        panic!("type was not correctly resolved")
+                    if generics.len() > 0 {
+                        return Err(LithiaET::CompilationError(format!("generic type {} is not supported", self.print())).at(self.1.clone()))
+                    }
+                    if base_type.0.len() > 1 {
+                        // there are no types declared in modules yet
+                        return Err(LithiaET::CompilationError(format!("unknown type {}", base_type.print())).at(self.1.clone()))
                     }
                     match base_type.0.first().unwrap().0.as_str() {
+                        "bool" => core::LLVMInt1Type(),
                         "u8" | "i8" => core::LLVMInt8Type(),
                         "u16" | "i16" => core::LLVMInt16Type(),
                         "u32" | "i32" => core::LLVMInt32Type(),
                         "u64" | "i64" => core::LLVMInt64Type(),
                         "u128" | "i128" => core::LLVMInt8Type(),
-                        "uptr" | "iptr" => {
-                            #[cfg(target_pointer_width = "16")]
-                                let t = core::LLVMInt8Type();
-                            #[cfg(target_pointer_width = "32")]
-                                let t = core::LLVMInt32Type();
-                            #[cfg(target_pointer_width = "64")]
-                                let t = core::LLVMInt64Type();
-                            t
+                        "uptr" | "iptr" => target::LLVMIntPtrType(target::LLVMGetModuleDataLayout(env.module)),
+                        name => {
+                            let candidates = PRIMITIVE_TYPES.iter().map(|t| Candidate::builtin(t, "a type")).collect::<Vec<_>>();
+                            return Err(suggest(LithiaET::CompilationError(format!("unknown type {}", base_type.print())).at(self.1.clone()), name, &candidates))
                         }
-                        t => unimplemented!("primitive type '{}' not figured out yet, come back tomorrow", t)
                     }
                 }
                 Ty::RawPointer => core::LLVMPointerType(core::LLVMVoidType(), 0), // TODO: replace 0 with adapting value
@@ -537,11 +663,12 @@ impl AstLiteral {
                             {
                                 let mut s = s.clone();
                                 s.push('\0');
-                                s.chars().map(|c| AstLiteral(Literal::Char(c), self.1.clone())).collect()
+                                // strings are UTF-8 encoded bytes
+                                s.bytes().map(|b| AstLiteral(Literal::Number(NumLit::Integer(b as u128), Some(NumLitTy::U8)), self.1.clone())).collect()
                             },
                             Type(Ty::Single(vec![], Item::new(&vec!["u8"], self.1.clone())), self.1.clone()),
                             s.len() + 1), self.1.clone()), env)?.llvm_value,
-                    Literal::Char(c) => core::LLVMConstInt(core::LLVMInt8Type(), *c as u8 as c_ulonglong, false as LLVMBool),
+                    Literal::Char(c) => core::LLVMConstInt(self.get_type()?.llvm_type(env)?, *c as c_ulonglong, false as LLVMBool),
                     Literal::Number(NumLit::Integer(num), _) => {
                         core::LLVMConstInt(self.get_type()?.llvm_type(env)?, *num as u8 as c_ulonglong, false as LLVMBool)
                     }
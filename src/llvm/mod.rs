@@ -1,15 +1,27 @@
 pub(crate) mod gen_llvm;
 pub(crate) mod llvm_ast;
 pub(crate) mod gen_flow_expressions;
+pub(crate) mod jit;
+pub(crate) mod target;
+pub(crate) mod resolve;
+pub(crate) mod debug_info;
+pub(crate) mod verify;
+pub(crate) mod lints;
+pub(crate) mod suggest;
 
 use std::collections::HashMap;
-use std::ffi::c_uint;
+use std::ffi::{c_char, c_uint, CStr};
 
 use llvm_sys::{prelude, core};
 use llvm_sys::prelude::{LLVMTypeRef, LLVMValueRef};
-use crate::ast::Type;
+use crate::ast::{Item, Ty, Type};
+use crate::ast::code_printer::CodePrinter;
 use crate::ast::types_impl::TySat::No;
-use crate::error::{LithiaError, LithiaET};
+use crate::error::{LithiaError, LithiaET, OnParseErr};
+use crate::llvm::debug_info::DebugInfo;
+use crate::llvm::lints::{Binding, LintState};
+use crate::llvm::resolve::ModScope;
+use crate::llvm::target::Target;
 use crate::source::span::Span;
 
 #[macro_export]
@@ -19,8 +31,7 @@ macro_rules! c_str {
         unsafe { std::ffi::CStr::from_ptr(concat!($s, "\0").as_ptr() as *const i8) }
     );
     ($s:expr) => (
-        #[allow(unused_unsafe)]
-        unsafe { std::ffi::CStr::from_ptr(($s.to_string() + "\0").as_ptr() as *const i8) }
+        std::ffi::CString::new($s.to_string()).unwrap()
     );
 }
 
@@ -31,17 +42,35 @@ macro_rules! c_str_ptr {
     );
 }
 
+/// Converts a message allocated by LLVM into a `String` and frees the original.
+pub(crate) unsafe fn take_llvm_message(msg: *mut c_char) -> String {
+    if msg.is_null() {
+        return String::new()
+    }
+    let s = CStr::from_ptr(msg).to_string_lossy().to_string();
+    core::LLVMDisposeMessage(msg);
+    s
+}
+
 pub(crate) struct LLVMModGenEnv {
     globals: HashMap<String, Variable>,
+    scopes: HashMap<Vec<String>, ModScope>,
     stack: Vec<StackEnv>,
     mod_name: String,
+    triple: String,
+    data_layout: String,
+    /// path of the sub module currently being built, empty for the root module
+    mod_path: Vec<String>,
     module: prelude::LLVMModuleRef,
     builder: prelude::LLVMBuilderRef,
-    function: Option<LLVMValueRef>
+    function: Option<LLVMValueRef>,
+    debug: Option<DebugInfo>,
+    lints: LintState
 }
 
 pub(crate) struct StackEnv {
     vars: HashMap<String, Variable>,
+    bindings: Vec<Binding>,
     opaque: bool,
     unsafe_ctx: bool,
 }
@@ -71,11 +100,24 @@ pub(crate) struct Variable{
     mutable: bool
 }
 
+/// Creates an empty module for the target, so types like `uptr` can be sized by its data layout.
+fn create_module(mod_name: &str, triple: &str, data_layout: &str) -> prelude::LLVMModuleRef{
+    unsafe {
+        let module = core::LLVMModuleCreateWithName(c_str_ptr!(mod_name));
+        core::LLVMSetTarget(module, c_str_ptr!(triple));
+        core::LLVMSetDataLayout(module, c_str_ptr!(data_layout));
+        module
+    }
+}
+
 impl LLVMModGenEnv{
-    pub(crate) fn new(mod_name: String) -> Self{
-        let module = unsafe { core::LLVMModuleCreateWithName(c_str_ptr!(mod_name)) };
+    pub(crate) fn new(mod_name: String, target: &Target) -> Self{
+        let data_layout = target.data_layout();
+        let module = create_module(&mod_name, &target.triple, &data_layout);
+        // int main(int argc, char** argv)
         let main_entrypoint_function_type = unsafe {
-            core::LLVMFunctionType(core::LLVMVoidType(), [].as_mut_ptr(), 0, 0)
+            let mut args = [core::LLVMInt32Type(), core::LLVMPointerType(core::LLVMPointerType(core::LLVMInt8Type(), 0), 0)];
+            core::LLVMFunctionType(core::LLVMInt32Type(), args.as_mut_ptr(), 2, 0)
         };
         let main_entrypoint_function = unsafe { core::LLVMAddFunction(module, c_str_ptr!("main"), main_entrypoint_function_type) };
         let entry_block = unsafe { core::LLVMAppendBasicBlock(main_entrypoint_function, c_str_ptr!("entry")) };
@@ -86,51 +128,181 @@ impl LLVMModGenEnv{
         };
         Self {
             globals: HashMap::new(),
+            scopes: HashMap::new(),
             stack: vec![],
             mod_name: mod_name.clone(),
+            triple: target.triple.clone(),
+            data_layout,
+            mod_path: vec![],
             module,
             builder,
-            function: None
+            function: None,
+            debug: None,
+            lints: LintState::default()
         }
     }
 
+    /// Creates an environment without the C `main` entry point, used for code that is not run as a program.
+    pub(crate) fn without_entrypoint(mod_name: String, target: &Target) -> Self{
+        let data_layout = target.data_layout();
+        let module = create_module(&mod_name, &target.triple, &data_layout);
+        Self {
+            globals: HashMap::new(),
+            scopes: HashMap::new(),
+            stack: vec![],
+            mod_name,
+            triple: target.triple.clone(),
+            data_layout,
+            mod_path: vec![],
+            module,
+            builder: unsafe { core::LLVMCreateBuilder() },
+            function: None,
+            debug: None,
+            lints: LintState::default()
+        }
+    }
+
+    /// Hands out the current module and continues in a fresh one in which all known globals are declared again,
+    /// so code built later can still refer to them once both modules are linked together.
+    pub(crate) fn take_module(&mut self) -> prelude::LLVMModuleRef{
+        let module = self.module;
+        unsafe {
+            self.module = create_module(&self.mod_name, &self.triple, &self.data_layout);
+            for var in self.globals.values_mut() {
+                // symbol names differ from the item paths for externs
+                let mut len = 0;
+                let name = CStr::from_ptr(core::LLVMGetValueName2(var.llvm_value, &mut len)).to_string_lossy().to_string();
+                var.llvm_value = if let Ty::Signature(..) = var.ast_type.0 {
+                    core::LLVMAddFunction(self.module, c_str_ptr!(name), var.llvm_type)
+                } else {
+                    core::LLVMAddGlobal(self.module, var.llvm_type, c_str_ptr!(name))
+                };
+            }
+        }
+        module
+    }
+
+    pub(crate) fn checkpoint(&self) -> (HashMap<String, Variable>, HashMap<Vec<String>, ModScope>){
+        (self.globals.clone(), self.scopes.clone())
+    }
+
+    /// Drops everything built since the checkpoint was taken.
+    pub(crate) fn rollback(&mut self, (globals, scopes): (HashMap<String, Variable>, HashMap<Vec<String>, ModScope>)){
+        self.globals = globals;
+        self.scopes = scopes;
+        // a failed build may have left its function state behind
+        self.stack.clear();
+        self.function = None;
+        self.reset_lints();
+        let module = self.take_module();
+        unsafe { core::LLVMDisposeModule(module) }
+    }
+
+    pub(crate) fn is_defined(&self, ident: &str) -> bool{
+        self.globals.contains_key(&self.item_path(ident))
+    }
+
+    pub(crate) fn enter_module(&mut self, name: &str){
+        self.mod_path.push(name.to_string())
+    }
+
+    pub(crate) fn exit_module(&mut self){
+        self.mod_path.pop();
+    }
+
+    /// Full path of an item `name` defined in the current module, items of the root module are not prefixed.
+    pub(crate) fn item_path(&self, name: &str) -> String{
+        self.mod_path.iter().map(String::as_str).chain([name]).collect::<Vec<_>>().join("::")
+    }
+
     pub(crate) fn push_stack(&mut self, opaque: bool, unsafe_ctx: bool){
         self.stack.push(StackEnv {
             vars: Default::default(),
+            bindings: vec![],
             opaque,
             unsafe_ctx: unsafe_ctx || (!opaque && self.stack.last().map(|s| s.unsafe_ctx).unwrap_or(false)),
         })
     }
 
     pub(crate) fn pop_stack(&mut self){
+        self.report_unused_bindings();
         self.stack.pop();
     }
 
     pub(crate) fn get_var(&self, ident: &str, loc: Option<&Span>) -> Result<Variable, LithiaError>{
+        self.find_var(ident, loc, true)
+    }
+
+    /// Like `get_var`, but does not count as reading the variable, for the target of an assignment.
+    pub(crate) fn get_assigned_var(&self, ident: &str, loc: Option<&Span>) -> Result<Variable, LithiaError>{
+        self.find_var(ident, loc, false)
+    }
+
+    fn find_var(&self, ident: &str, loc: Option<&Span>, read: bool) -> Result<Variable, LithiaError>{
         for frame in self.stack.iter().rev(){
             if let Some(v) = frame.vars.get(ident){
+                if read {
+                    frame.mark_used(ident);
+                }
                 return Ok(v.clone())
             }
             if frame.opaque { break }
         }
-        if let Some(v) = self.globals.get(ident){
-            Ok(v.clone())
-        } else {
-            let et = LithiaET::VariableNotFound(ident.to_string());
-            Err(match loc {
-                None => et.error(),
-                Some(loc) => et.at(loc.clone())
-            })
-        }
+        // items of the current module shadow the ones of the root module
+        self.get_item(&[ident.to_string()], loc)
     }
 
+    /// Calls the `main` of the module from the C entry point, `main` may take `argc: i32, argv: &&u8`
+    /// which are forwarded and may return an `i32` exit code, otherwise the process exits with `0`.
     pub(crate) fn finish(self) -> Result<prelude::LLVMModuleRef, LithiaError>{
+        match self.call_main() {
+            Ok(()) => self.finish_module(),
+            Err(e) => Err(self.discard(e))
+        }
+    }
+
+    fn call_main(&self) -> Result<(), LithiaError>{
+        let fun = self.get_var("main", None).e_when("looking for the main function, build libraries with --crate-type")?;
+        let loc = fun.ast_type.1.clone();
+        let Ty::Signature(args, ret, _, _) = &fun.ast_type.0 else {
+            return Err(LithiaET::CompilationError("main has to be a function".to_string()).at(loc))
+        };
+        let i32_type = Type(Ty::Single(vec![], Item::new(&vec!["i32"], loc.clone())), loc.clone());
+        let argv_type = Type(Ty::Pointer(Box::new(Type(Ty::Pointer(Box::new(Type(Ty::Single(vec![], Item::new(&vec!["u8"], loc.clone())), loc.clone()))), loc.clone()))), loc.clone());
+        let forward_args = match args.as_slice() {
+            [] => false,
+            [argc, argv] if *argc == i32_type && *argv == argv_type => true,
+            _ => return Err(LithiaET::CompilationError(format!("main has to take no arguments or (argc: {}, argv: {})", i32_type.print(), argv_type.print())).at(loc))
+        };
+        let returns_code = if ret.0.is_empty() {
+            false
+        } else if **ret == i32_type {
+            true
+        } else {
+            return Err(LithiaET::CompilationError(format!("main has to return () or {}, found {}", i32_type.print(), ret.print())).at_label(ret.1.clone(), format!("returns {} here", ret.print())).at_add(loc))
+        };
         unsafe {
-            let fun = self.get_var("main", None)?;
-            core::LLVMBuildCall2(self.builder, fun.llvm_type, fun.llvm_value, [].as_mut_ptr(), 0 as c_uint, c_str_ptr!(""));
-            core::LLVMBuildRetVoid(self.builder);
+            let entrypoint = core::LLVMGetBasicBlockParent(core::LLVMGetInsertBlock(self.builder));
+            let mut args = if forward_args { vec![core::LLVMGetParam(entrypoint, 0), core::LLVMGetParam(entrypoint, 1)] } else { vec![] };
+            let code = core::LLVMBuildCall2(self.builder, fun.llvm_type, fun.llvm_value, args.as_mut_ptr(), args.len() as c_uint, c_str_ptr!(""));
+            core::LLVMBuildRet(self.builder, if returns_code { code } else { core::LLVMConstInt(core::LLVMInt32Type(), 0, 0) });
+        }
+        Ok(())
+    }
+
+    /// Finishes a module without calling into the `main` of the module, see `without_entrypoint`.
+    pub(crate) fn finish_module(mut self) -> Result<prelude::LLVMModuleRef, LithiaError>{
+        self.finish_debug_info();
+        match self.verify_module() {
+            Ok(()) => Ok(self.module),
+            Err(e) => Err(self.discard(e))
         }
-        Ok(self.module)
+    }
+
+    /// Disposes the module of a failed build and passes the error on.
+    pub(crate) fn discard<E>(self, e: E) -> E{
+        unsafe { core::LLVMDisposeModule(self.module) }
+        e
     }
 }
 
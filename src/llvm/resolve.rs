@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use crate::ast::{Import, Module};
+use crate::diagnostics::Diagnostics;
+use crate::error::{LithiaError, LithiaET};
+use crate::llvm::{LLVMModGenEnv, Variable};
+use crate::llvm::suggest::{suggest, Candidate};
+use crate::source::span::Span;
+
+/// Names declared in a module with where they are declared, paths are resolved through these.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ModScope {
+    items: HashMap<String, Span>,
+    modules: HashMap<String, Span>,
+    imports: HashMap<String, Import>
+}
+
+impl ModScope {
+    fn contains(&self, name: &str) -> bool {
+        self.items.contains_key(name) || self.modules.contains_key(name) || self.imports.contains_key(name)
+    }
+
+    /// All declared names, to suggest one of them for a misspelled name.
+    pub(crate) fn candidates(&self) -> Vec<Candidate> {
+        let mut candidates = self.items.iter().map(|(n, loc)| Candidate::new(n, "an item", loc))
+            .chain(self.modules.iter().map(|(n, loc)| Candidate::new(n, "a module", loc)))
+            .chain(self.imports.iter().map(|(n, i)| Candidate::new(n, "an import", &i.name.1)))
+            .collect::<Vec<_>>();
+        // the maps have no stable order
+        candidates.sort_by(|a, b| a.name.cmp(&b.name));
+        candidates
+    }
+}
+
+enum Resolved {
+    Module(Vec<String>),
+    Item(Vec<String>)
+}
+
+/// Imports referring to each other deeper than this are considered cyclic.
+const MAX_IMPORT_DEPTH: usize = 64;
+
+impl LLVMModGenEnv {
+    /// Adds the names declared in `module` to the scope of the current module.
+    pub(crate) fn declare_scope(&mut self, module: &Module) {
+        let scope = self.scopes.entry(self.mod_path.clone()).or_default();
+        scope.items.extend(module.functions.iter().map(|(n, f)| (n.clone(), f.name.1.clone())));
+        scope.items.extend(module.constants.iter().map(|(n, c)| (n.clone(), c.name.1.clone())));
+        scope.modules.extend(module.sub_modules.iter().map(|(n, m)| (n.clone(), m.name.1.clone())));
+        scope.imports.extend(module.imports.iter().map(|(n, i)| (n.clone(), i.clone())));
+    }
+
+    /// Resolves every import, even unused ones, so broken paths are reported.
+    pub(crate) fn check_imports(&self, diagnostics: &mut Diagnostics) {
+        for (mod_path, scope) in &self.scopes {
+            for import in scope.imports.values() {
+                let segments = import.path.0.iter().map(|i| i.0.clone()).collect::<Vec<_>>();
+                if let Err(e) = self.resolve(mod_path, &segments, &import.path.1, 0) {
+                    diagnostics.push(e)
+                }
+            }
+        }
+    }
+
+    /// Looks up the item at `path` as seen from the current module.
+    /// The first segment is searched in the current module, then in the root module.
+    pub(crate) fn get_item(&self, path: &[String], loc: Option<&Span>) -> Result<Variable, LithiaError> {
+        let not_found = || {
+            let et = LithiaET::VariableNotFound(path.join("::"));
+            let error = match loc {
+                None => et.error(),
+                Some(loc) => et.at(loc.clone())
+            };
+            self.suggest_variable(error, path.last().unwrap())
+        };
+        let Some(loc) = loc else {
+            return self.globals.get(&self.item_path(&path.join("::"))).cloned().ok_or_else(not_found)
+        };
+        if path.len() == 1 && !self.declares(&self.mod_path, &path[0]) && !self.declares(&[], &path[0]) {
+            return Err(not_found())
+        }
+        match self.resolve(&self.mod_path, path, loc, 0)? {
+            Resolved::Item(p) => self.globals.get(&p.join("::")).cloned().ok_or_else(not_found),
+            Resolved::Module(p) => Err(LithiaET::CompilationError(format!("expected item, found module {}", self.display_module(&p))).at(loc.clone()))
+        }
+    }
+
+    /// Names declared in the module, for suggestions.
+    pub(crate) fn module_candidates(&self, mod_path: &[String]) -> Vec<Candidate> {
+        self.scopes.get(mod_path).map(ModScope::candidates).unwrap_or_default()
+    }
+
+    fn declares(&self, mod_path: &[String], name: &str) -> bool {
+        self.scopes.get(mod_path).map(|s| s.contains(name)).unwrap_or(false)
+    }
+
+    fn display_module(&self, mod_path: &[String]) -> String {
+        if mod_path.is_empty() {
+            self.mod_name.clone()
+        } else {
+            mod_path.join("::")
+        }
+    }
+
+    fn resolve(&self, from: &[String], path: &[String], loc: &Span, depth: usize) -> Result<Resolved, LithiaError> {
+        if depth > MAX_IMPORT_DEPTH {
+            return Err(LithiaET::CompilationError(format!("cyclic import of {}", path.join("::"))).at(loc.clone()))
+        }
+        let not_found = |name: &str, module: &[String]| suggest(
+            LithiaET::NotFoundInModule(name.to_string(), self.display_module(module)).at(loc.clone()),
+            name,
+            &self.module_candidates(module)
+        );
+        let (mut current, rest) = match path[0].as_str() {
+            "crate" => (vec![], &path[1..]),
+            "super" => {
+                let mut current = from.to_vec();
+                let supers = path.iter().take_while(|s| *s == "super").count();
+                for _ in 0..supers {
+                    if current.pop().is_none() {
+                        return Err(LithiaET::CompilationError("super of the root module".to_string()).at(loc.clone()))
+                    }
+                }
+                (current, &path[supers..])
+            }
+            first => if self.declares(from, first) {
+                (from.to_vec(), path)
+            } else if self.declares(&[], first) {
+                (vec![], path)
+            } else {
+                let mut candidates = self.module_candidates(from);
+                if !from.is_empty() {
+                    candidates.extend(self.module_candidates(&[]));
+                }
+                return Err(suggest(LithiaET::NotFoundInModule(first.to_string(), self.display_module(from)).at(loc.clone()), first, &candidates))
+            }
+        };
+        for (i, segment) in rest.iter().enumerate() {
+            let last = i == rest.len() - 1;
+            let Some(scope) = self.scopes.get(&current) else {
+                return Err(not_found(segment, &current))
+            };
+            if !last && scope.modules.contains_key(segment) {
+                current.push(segment.clone());
+            } else if scope.items.contains_key(segment) {
+                if !last {
+                    return Err(LithiaET::CompilationError(format!("{segment} is not a module")).at(loc.clone()))
+                }
+                current.push(segment.clone());
+                return Ok(Resolved::Item(current))
+            } else if let Some(import) = scope.imports.get(segment) {
+                let target = import.path.0.iter().map(|i| i.0.clone()).collect::<Vec<_>>();
+                match self.resolve(&current, &target, loc, depth + 1)? {
+                    Resolved::Module(m) => current = m,
+                    Resolved::Item(item) if last => return Ok(Resolved::Item(item)),
+                    Resolved::Item(_) => return Err(LithiaET::CompilationError(format!("{segment} is not a module")).at(loc.clone()))
+                }
+            } else if scope.modules.contains_key(segment) {
+                current.push(segment.clone());
+            } else {
+                return Err(not_found(segment, &current))
+            }
+        }
+        Ok(Resolved::Module(current))
+    }
+}
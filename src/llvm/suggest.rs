@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use crate::ast::Tag;
+use crate::error::{LithiaError, LithiaET};
+use crate::llvm::LLVMModGenEnv;
+use crate::source::span::Span;
+use crate::util::suggest::closest;
+
+/// Types built into the language.
+pub(crate) const PRIMITIVE_TYPES: [&str; 13] = ["bool", "u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64", "u128", "i128", "uptr", "iptr"];
+
+/// Tags understood on functions.
+pub(crate) const FUNCTION_TAGS: [&str; 8] = ["unsafe", "extern", "vararg", "test", "allow", "warn", "deny", "doc"];
+
+/// Tags understood on expressions.
+pub(crate) const EXPRESSION_TAGS: [&str; 4] = ["unsafe", "allow", "warn", "deny"];
+
+/// A name which could have been meant instead of an unknown one.
+#[derive(Debug, Clone)]
+pub(crate) struct Candidate {
+    pub(crate) name: String,
+    /// what the name refers to, e.g. `a variable`
+    kind: &'static str,
+    loc: Option<Span>
+}
+
+impl Candidate {
+    pub(crate) fn new(name: &str, kind: &'static str, loc: &Span) -> Self {
+        Self { name: name.to_string(), kind, loc: Some(loc.clone()) }
+    }
+
+    /// A name without a location, like a builtin type.
+    pub(crate) fn builtin(name: &str, kind: &'static str) -> Self {
+        Self { name: name.to_string(), kind, loc: None }
+    }
+}
+
+/// Adds a help naming the candidate closest to `name` and a label where it is defined.
+pub(crate) fn suggest(error: LithiaError, name: &str, candidates: &[Candidate]) -> LithiaError {
+    let Some(closest) = closest(name, candidates.iter().map(|c| c.name.as_str())) else {
+        return error
+    };
+    let candidate = candidates.iter().find(|c| c.name == closest).unwrap();
+    let error = error.help(format!("{} with a similar name exists: {}", candidate.kind, candidate.name));
+    match &candidate.loc {
+        Some(loc) => error.at_label(loc.clone(), format!("{} defined here", candidate.name)),
+        None => error
+    }
+}
+
+/// Fails for tags not in `known`, suggesting a known one.
+pub(crate) fn check_tags(tags: &HashMap<String, Tag>, known: &[&str], on: &str) -> Result<(), LithiaError> {
+    let mut unknown = tags.values().filter(|t| !known.contains(&t.0.0.as_str())).collect::<Vec<_>>();
+    unknown.sort_by_key(|t| t.2.start);
+    let Some(tag) = unknown.first() else {
+        return Ok(())
+    };
+    let candidates = known.iter().map(|t| Candidate::builtin(t, "a tag")).collect::<Vec<_>>();
+    Err(suggest(LithiaET::TagError(format!("unknown tag #[{}] on {on}", tag.0.0)).at(tag.0.1.clone()), &tag.0.0, &candidates))
+}
+
+impl LLVMModGenEnv {
+    /// Variables of the current function followed by the items visible from the current module.
+    fn visible_candidates(&self) -> Vec<Candidate> {
+        let mut candidates = vec![];
+        for frame in self.stack.iter().rev() {
+            candidates.extend(frame.bindings.iter().rev().map(|b| Candidate::new(&b.name, "a variable", &b.loc)));
+            if frame.opaque { break }
+        }
+        candidates.extend(self.module_candidates(&self.mod_path));
+        if !self.mod_path.is_empty() {
+            candidates.extend(self.module_candidates(&[]));
+        }
+        candidates
+    }
+
+    /// Suggests a variable or item for a name that could not be found.
+    pub(crate) fn suggest_variable(&self, error: LithiaError, name: &str) -> LithiaError {
+        suggest(error, name, &self.visible_candidates())
+    }
+
+    /// Where the variable `name` of the current function was bound.
+    pub(crate) fn binding_loc(&self, name: &str) -> Option<Span> {
+        for frame in self.stack.iter().rev() {
+            if let Some(binding) = frame.bindings.iter().rev().find(|b| b.name == name) {
+                return Some(binding.loc.clone())
+            }
+            if frame.opaque { break }
+        }
+        None
+    }
+}
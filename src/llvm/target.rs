@@ -0,0 +1,154 @@
+use std::ffi::CStr;
+use std::path::Path;
+use std::ptr;
+use llvm_sys::{core, error, prelude, target, target_machine};
+use llvm_sys::transforms::pass_builder;
+use llvm_sys::target_machine::{LLVMCodeGenFileType, LLVMCodeGenOptLevel, LLVMCodeModel, LLVMRelocMode, LLVMTargetMachineRef};
+use crate::c_str_ptr;
+use crate::error::{LithiaError, LithiaET};
+use crate::llvm::take_llvm_message;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum OptLevel {
+    O0,
+    O1,
+    O2,
+    O3,
+    Os
+}
+
+impl OptLevel {
+    pub(crate) fn from_flag(flag: &str) -> Option<Self> {
+        Some(match flag {
+            "-O0" => OptLevel::O0,
+            "-O1" => OptLevel::O1,
+            "-O2" => OptLevel::O2,
+            "-O3" => OptLevel::O3,
+            "-Os" => OptLevel::Os,
+            _ => return None
+        })
+    }
+
+    /// The default pipeline of the new pass manager for this level.
+    fn pipeline(&self) -> &'static str {
+        match self {
+            OptLevel::O0 => "default<O0>",
+            OptLevel::O1 => "default<O1>",
+            OptLevel::O2 => "default<O2>",
+            OptLevel::O3 => "default<O3>",
+            OptLevel::Os => "default<Os>"
+        }
+    }
+
+    fn codegen_level(&self) -> LLVMCodeGenOptLevel {
+        match self {
+            OptLevel::O0 => LLVMCodeGenOptLevel::LLVMCodeGenLevelNone,
+            OptLevel::O1 => LLVMCodeGenOptLevel::LLVMCodeGenLevelLess,
+            OptLevel::O2 | OptLevel::Os => LLVMCodeGenOptLevel::LLVMCodeGenLevelDefault,
+            OptLevel::O3 => LLVMCodeGenOptLevel::LLVMCodeGenLevelAggressive
+        }
+    }
+}
+
+/// Wrapper around an LLVM `TargetMachine` used to emit assembly and object files.
+pub(crate) struct Target {
+    machine: LLVMTargetMachineRef,
+    pub(crate) triple: String
+}
+
+impl Target {
+    pub(crate) fn host() -> Result<Self, LithiaError> {
+        Self::new(None, OptLevel::O0)
+    }
+
+    /// Creates a machine for `triple` with its default CPU, or for the host including its exact CPU and features if it is `None`.
+    pub(crate) fn new(triple: Option<&str>, opt_level: OptLevel) -> Result<Self, LithiaError> {
+        unsafe {
+            let (triple, cpu, features) = match triple {
+                None => {
+                    if target::LLVM_InitializeNativeTarget() != 0 || target::LLVM_InitializeNativeAsmPrinter() != 0 {
+                        return Err(LithiaET::LLVMError("could not initialize native target".to_string()).error())
+                    }
+                    (take_llvm_message(target_machine::LLVMGetDefaultTargetTriple()),
+                     take_llvm_message(target_machine::LLVMGetHostCPUName()),
+                     take_llvm_message(target_machine::LLVMGetHostCPUFeatures()))
+                }
+                Some(triple) => {
+                    target::LLVM_InitializeAllTargetInfos();
+                    target::LLVM_InitializeAllTargets();
+                    target::LLVM_InitializeAllTargetMCs();
+                    target::LLVM_InitializeAllAsmPrinters();
+                    (take_llvm_message(target_machine::LLVMNormalizeTargetTriple(c_str_ptr!(triple))), String::new(), String::new())
+                }
+            };
+            let mut t = ptr::null_mut();
+            let mut err = ptr::null_mut();
+            if target_machine::LLVMGetTargetFromTriple(c_str_ptr!(triple), &mut t, &mut err) != 0 {
+                return Err(LithiaET::LLVMError(take_llvm_message(err)).error().when(format!("looking up target {triple}")))
+            }
+            let machine = target_machine::LLVMCreateTargetMachine(t, c_str_ptr!(triple), c_str_ptr!(cpu), c_str_ptr!(features),
+                                                                  opt_level.codegen_level(),
+                                                                  LLVMRelocMode::LLVMRelocPIC,
+                                                                  LLVMCodeModel::LLVMCodeModelDefault);
+            Ok(Self {
+                machine,
+                triple
+            })
+        }
+    }
+
+    /// The data layout of this target as a string, as used by `LLVMSetDataLayout`.
+    pub(crate) fn data_layout(&self) -> String {
+        unsafe {
+            let layout = target_machine::LLVMCreateTargetDataLayout(self.machine);
+            let s = take_llvm_message(target::LLVMCopyStringRepOfTargetData(layout));
+            target::LLVMDisposeTargetData(layout);
+            s
+        }
+    }
+
+    /// Sets the triple and data layout of the module to the ones of this target.
+    pub(crate) fn configure_module(&self, module: prelude::LLVMModuleRef) {
+        unsafe {
+            core::LLVMSetTarget(module, c_str_ptr!(self.triple));
+            let layout = target_machine::LLVMCreateTargetDataLayout(self.machine);
+            target::LLVMSetModuleDataLayout(module, layout);
+            target::LLVMDisposeTargetData(layout);
+        }
+    }
+
+    /// Runs the default optimization pipeline of `level` on the module.
+    pub(crate) fn optimize(&self, module: prelude::LLVMModuleRef, level: OptLevel) -> Result<(), LithiaError> {
+        self.configure_module(module);
+        unsafe {
+            let options = pass_builder::LLVMCreatePassBuilderOptions();
+            let err = pass_builder::LLVMRunPasses(module, c_str_ptr!(level.pipeline()), self.machine, options);
+            pass_builder::LLVMDisposePassBuilderOptions(options);
+            if !err.is_null() {
+                let msg = error::LLVMGetErrorMessage(err);
+                let message = CStr::from_ptr(msg).to_string_lossy().to_string();
+                error::LLVMDisposeErrorMessage(msg);
+                return Err(LithiaET::LLVMError(message).error().when(format!("running {}", level.pipeline())))
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn emit_to_file<P: AsRef<Path>>(&self, module: prelude::LLVMModuleRef, file: P, file_type: LLVMCodeGenFileType) -> Result<(), LithiaError> {
+        let file = file.as_ref().to_string_lossy().to_string();
+        self.configure_module(module);
+        unsafe {
+            let mut err = ptr::null_mut();
+            if target_machine::LLVMTargetMachineEmitToFile(self.machine, module, c_str_ptr!(file) as *mut _, file_type, &mut err) != 0 {
+                return Err(LithiaET::LLVMError(take_llvm_message(err)).error().when(format!("emitting {file}")))
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Target {
+    fn drop(&mut self) {
+        unsafe { target_machine::LLVMDisposeTargetMachine(self.machine) }
+    }
+}
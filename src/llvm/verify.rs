@@ -0,0 +1,42 @@
+use std::ptr;
+use llvm_sys::analysis::{LLVMVerifierFailureAction, LLVMVerifyFunction, LLVMVerifyModule};
+use llvm_sys::core;
+use crate::error::{LithiaError, LithiaET};
+use crate::llvm::{take_llvm_message, LLVMModGenEnv};
+
+impl LLVMModGenEnv {
+    /// Checks the generated IR, so invalid code is reported at the functions it was built for
+    /// instead of crashing the backend later on. Has to run after the debug info was finalized.
+    pub(crate) fn verify_module(&self) -> Result<(), LithiaError> {
+        let mut message = ptr::null_mut();
+        let broken = unsafe { LLVMVerifyModule(self.module, LLVMVerifierFailureAction::LLVMReturnStatusAction, &mut message) } != 0;
+        let message = unsafe { take_llvm_message(message) };
+        if !broken {
+            return Ok(())
+        }
+        let mut names = vec![];
+        let mut locs = vec![];
+        unsafe {
+            let mut function = core::LLVMGetFirstFunction(self.module);
+            while !function.is_null() {
+                if LLVMVerifyFunction(function, LLVMVerifierFailureAction::LLVMReturnStatusAction) != 0 {
+                    // generated functions like the entry point have no definition to point at
+                    match self.globals.iter().find(|(_, var)| var.llvm_value == function) {
+                        Some((path, var)) => {
+                            names.push(path.clone());
+                            locs.push(var.ast_type.1.clone());
+                        }
+                        None => {
+                            let mut len = 0;
+                            let name = core::LLVMGetValueName2(function, &mut len);
+                            names.push(String::from_utf8_lossy(std::slice::from_raw_parts(name as *const u8, len)).to_string());
+                        }
+                    }
+                }
+                function = core::LLVMGetNextFunction(function);
+            }
+        }
+        let name = if names.is_empty() { self.mod_name.clone() } else { names.join(", ") };
+        Err(LithiaET::VerificationError(name, message.trim_end().to_string()).ats(locs))
+    }
+}
@@ -10,24 +10,34 @@
 extern crate core;
 
 use std::process::exit;
-use crate::compiler::{compile, Arguments};
+use crate::compiler::{compile, Arguments, USAGE};
 
 pub(crate) mod ast;
 pub(crate) mod llvm;
 pub(crate) mod source;
 pub(crate) mod tokens;
 pub(crate) mod error;
+pub(crate) mod error_codes;
+pub(crate) mod lints;
+pub(crate) mod diagnostics;
 pub(crate) mod compiler;
+pub(crate) mod repl;
+pub(crate) mod test_runner;
 pub(crate) mod util;
 
 fn main() {
-   let args = Arguments {
-
+   let args = match Arguments::from_args(std::env::args().skip(1)) {
+      Ok(args) => args,
+      Err(e) => {
+         println!("{e}\n\n{USAGE}");
+         exit(2)
+      }
    };
+   let error_format = args.error_format;
    match compile(args) {
-      Ok(_) => (),
-      Err(e) => {
-         println!("{e}");
+      Ok(code) => exit(code),
+      Err(diagnostics) => {
+         diagnostics.print(error_format);
          exit(1)
       }
    }
@@ -0,0 +1,195 @@
+use std::io::{stdin, stdout, Write};
+use llvm_sys::{core, target, LLVMTypeKind};
+use llvm_sys::prelude::LLVMTypeRef;
+use crate::ast::code_printer::CodePrinter;
+use crate::ast::parser::{item_keyword, parse, parse_expression};
+use crate::ast::{Module, Ty, Type};
+use crate::compiler::Arguments;
+use crate::diagnostics::Diagnostics;
+use crate::error::{LithiaError, LithiaET};
+use crate::llvm::gen_llvm::build_repl_snippet;
+use crate::llvm::jit::Jit;
+use crate::llvm::LLVMModGenEnv;
+use crate::llvm::target::Target;
+use crate::source::Source;
+use crate::tokens::{Token, TokenType};
+use crate::tokens::tokenizer::tokenize;
+
+/// Keeps all definitions entered so far and jit compiles every new input on top of them.
+struct Repl {
+    env: LLVMModGenEnv,
+    jit: Option<Jit>,
+    snippets: usize
+}
+
+pub(crate) fn repl(args: Arguments) -> Result<i32, Diagnostics> {
+    let mut repl = Repl {
+        env: LLVMModGenEnv::without_entrypoint("repl".to_string(), &Target::host()?),
+        jit: None,
+        snippets: 0
+    };
+    repl.env.set_lint_levels(args.lint_levels.clone());
+    if !args.input.as_os_str().is_empty() {
+        let source = Source::from_file(args.input.to_string_lossy().to_string())?;
+        repl.items(parse(tokenize(source)?, (args.module_name(), None))?)?;
+    }
+    println!("lithia repl, enter items or expressions, :q to quit");
+    let mut input = String::new();
+    loop {
+        print!("{}", if input.is_empty() { ">> " } else { ".. " });
+        stdout().flush()?;
+        let mut line = String::new();
+        if stdin().read_line(&mut line)? == 0 {
+            break
+        }
+        if input.is_empty() && matches!(line.trim(), ":q" | ":quit") {
+            break
+        }
+        input.push_str(&line);
+        if !is_complete(&input) {
+            continue
+        }
+        let snippet = std::mem::take(&mut input);
+        if snippet.trim().is_empty() {
+            continue
+        }
+        match repl.eval(snippet) {
+            Ok(Some(value)) => println!("{value}"),
+            Ok(None) => (),
+            // the input is discarded, there is nothing to abort
+            Err(diagnostics) => for e in diagnostics.errors() {
+                println!("{e}\n")
+            }
+        }
+    }
+    let module = repl.env.take_module();
+    unsafe { core::LLVMDisposeModule(module) }
+    Ok(0)
+}
+
+impl Repl {
+    fn eval(&mut self, snippet: String) -> Result<Option<String>, Diagnostics> {
+        let tokens = tokenize(Source::from_string(snippet))?;
+        if starts_with_item(&tokens) {
+            self.items(parse(tokens, ("repl".to_string(), None))?)?;
+            Ok(None)
+        } else {
+            let expr = parse_expression(tokens)?;
+            self.snippets += 1;
+            let name = format!("__repl_{}", self.snippets);
+            let checkpoint = self.env.checkpoint();
+            let result = match build_repl_snippet(&mut self.env, &expr, &name).and_then(|r| self.env.verify_module().map(|_| r)) {
+                Ok(result) => result,
+                Err(e) => {
+                    self.env.rollback(checkpoint);
+                    return Err(e.into())
+                }
+            };
+            let mut lints = self.env.take_lints();
+            if let Err(e) = lints.check() {
+                self.env.rollback(checkpoint);
+                return Err(e)
+            }
+            print_warnings(&lints);
+            let jit = self.submit()?;
+            jit.call_void(&name)?;
+            Ok(result.map(|(ty, llvm_ty)| {
+                let addr = jit.global_address(&format!("{name}_result"))?;
+                Ok::<_, LithiaError>(format!("{}: {}", unsafe { read_value(jit, addr as *const u8, &ty, llvm_ty) }, ty.print()))
+            }).transpose()?)
+        }
+    }
+
+    fn items(&mut self, module: Module) -> Result<(), Diagnostics> {
+        let mut diagnostics = Diagnostics::new();
+        for (name, item) in module.functions.iter().map(|(n, f)| (n, &f.name)).chain(module.constants.iter().map(|(n, c)| (n, &c.name))) {
+            if self.env.is_defined(name) {
+                diagnostics.push(LithiaET::AlreadyDefinedError("item".to_string(), name.clone()).at(item.1.clone()))
+            }
+        }
+        diagnostics.check()?;
+        let checkpoint = self.env.checkpoint();
+        match module.build(&mut self.env).and_then(|warnings| Ok(self.env.verify_module().map(|_| warnings)?)) {
+            Ok(warnings) => print_warnings(&warnings),
+            Err(e) => {
+                self.env.rollback(checkpoint);
+                return Err(e)
+            }
+        }
+        self.submit()?;
+        Ok(())
+    }
+
+    /// Moves the module built so far into the jit.
+    fn submit(&mut self) -> Result<&Jit, LithiaError> {
+        let module = self.env.take_module();
+        match &self.jit {
+            Some(jit) => jit.add_module(module),
+            None => self.jit = Some(Jit::new(module)?)
+        }
+        Ok(self.jit.as_ref().unwrap())
+    }
+}
+
+fn print_warnings(diagnostics: &Diagnostics) {
+    for warning in diagnostics.all() {
+        println!("{warning}\n")
+    }
+}
+
+/// Whether the input tokenizes and all brackets are closed, otherwise it is continued on the next line.
+fn is_complete(input: &str) -> bool {
+    match tokenize(Source::from_string(input.to_string())) {
+        Ok(tokens) => tokens.iter().map(|t| match t.tt {
+            TokenType::Particle('{' | '(' | '[', _) => 1,
+            TokenType::Particle('}' | ')' | ']', _) => -1,
+            _ => 0
+        }).sum::<isize>() <= 0,
+        // strings, chars and block comments that are not closed yet reach the end of the input
+        Err(e) => !e.is_eof()
+    }
+}
+
+fn starts_with_item(tokens: &[Token]) -> bool {
+    matches!(item_keyword(tokens), Some("fn" | "const" | "mod" | "use"))
+}
+
+unsafe fn read_value(jit: &Jit, ptr: *const u8, ty: &Type, llvm_ty: LLVMTypeRef) -> String {
+    match core::LLVMGetTypeKind(llvm_ty) {
+        LLVMTypeKind::LLVMIntegerTypeKind => {
+            let width = core::LLVMGetIntTypeWidth(llvm_ty);
+            let bits = match width {
+                1 => (*ptr & 1) as u64,
+                8 => *ptr as u64,
+                16 => (ptr as *const u16).read_unaligned() as u64,
+                32 => (ptr as *const u32).read_unaligned() as u64,
+                _ => (ptr as *const u64).read_unaligned()
+            };
+            let signed = matches!(&ty.0, Ty::Single(_, name) if name.0.len() == 1 && name.0[0].0.starts_with('i'));
+            if width == 1 {
+                format!("{}", bits != 0)
+            } else if signed && width < 64 {
+                let shift = 64 - width;
+                format!("{}", ((bits << shift) as i64) >> shift)
+            } else if signed {
+                format!("{}", bits as i64)
+            } else {
+                format!("{bits}")
+            }
+        }
+        LLVMTypeKind::LLVMPointerTypeKind => format!("{:#x}", (ptr as *const usize).read_unaligned()),
+        LLVMTypeKind::LLVMArrayTypeKind => {
+            let elem_ty = core::LLVMGetElementType(llvm_ty);
+            let elem_size = target::LLVMABISizeOfType(jit.target_data(), elem_ty) as usize;
+            let elem_ast_ty = match &ty.0 {
+                Ty::Array(t, _) | Ty::Slice(t) => *t.clone(),
+                _ => ty.clone()
+            };
+            let elems = (0..core::LLVMGetArrayLength(llvm_ty) as usize)
+                .map(|i| read_value(jit, ptr.add(i * elem_size), &elem_ast_ty, elem_ty))
+                .collect::<Vec<_>>();
+            format!("[{}]", elems.join(", "))
+        }
+        _ => "<value>".to_string()
+    }
+}
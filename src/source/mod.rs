@@ -1,3 +1,4 @@
+pub(crate) mod snippet;
 pub(crate) mod span;
 
 use std::fmt::{Debug, Display, Formatter};
@@ -12,7 +13,11 @@ use crate::source::span::Span;
 #[derive(PartialEq)]
 pub(crate) struct Source {
     st: SourceType,
-    source: String
+    source: String,
+    /// byte index at which each line starts, computed once so positions are found by binary search
+    line_starts: Vec<usize>,
+    /// decoded chars with their byte index, the tokenizer indexes these
+    chars: Vec<(usize, char)>
 }
 
 pub(crate) type SourceIter = Indexer<Rc<Source>>;
@@ -21,15 +26,16 @@ impl Indexable for Rc<Source> {
     const ITEM_NAME: &'static str = "char";
 
     fn get(&self, i: usize) -> Self::Item {
-        self.source.as_bytes()[i] as char
+        self.chars[i].1
     }
 
+    /// Spans keep byte indices, `i` is the index of the char.
     fn loc_at(&self, i: usize) -> Span {
-        CodePoint(self.clone(), i).span()
+        CodePoint(self.clone(), self.chars[i].0).span()
     }
 
     fn len(&self) -> usize {
-        self.source.len()
+        self.chars.len()
     }
 }
 
@@ -41,21 +47,65 @@ impl Debug for Source {
 
 impl Source {
     pub(crate) fn from_file<P: AsRef<Path> + Display>(path: P) -> Result<Self, LithiaError> {
-        Ok(Self {
-            st: SourceType::File(path.to_string()),
-            source: {
-                let mut f = File::open(path)?;
-                let mut buffer = String::new();
-                f.read_to_string(&mut buffer)?;
-                buffer
-            }
-        })
+        let source = {
+            let mut f = File::open(&path)?;
+            let mut buffer = String::new();
+            f.read_to_string(&mut buffer)?;
+            buffer
+        };
+        Ok(Self::new(SourceType::File(path.to_string()), source))
     }
 
     pub(crate) fn from_string(source: String) -> Self{
+        Self::new(SourceType::String, source)
+    }
+
+    fn new(st: SourceType, source: String) -> Self {
+        let line_starts = [0].into_iter()
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let chars = source.char_indices().collect();
         Self {
-            st: SourceType::String,
-            source
+            st,
+            source,
+            line_starts,
+            chars
+        }
+    }
+
+    /// Line (starting at 1) and column in chars (starting at 0) of the byte at `index`.
+    pub(crate) fn position(&self, index: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|start| *start <= index);
+        let line_start = self.line_starts[line - 1];
+        (line, self.source[line_start..index.min(self.source.len())].chars().count())
+    }
+
+    /// Byte index after the char starting at `index`.
+    pub(crate) fn char_end(&self, index: usize) -> usize {
+        index + self.source.get(index..).and_then(|s| s.chars().next()).map(char::len_utf8).unwrap_or(1)
+    }
+
+    /// Text of the line starting at 1, without its line break.
+    pub(crate) fn line(&self, line: usize) -> Option<&str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self.line_starts.get(line).map(|next| next - 1).unwrap_or(self.source.len());
+        Some(&self.source[start..end])
+    }
+
+    /// Number of lines, a trailing line break does not start another line.
+    pub(crate) fn line_count(&self) -> usize {
+        if self.line_starts.len() > 1 && self.source.ends_with('\n') {
+            self.line_starts.len() - 1
+        } else {
+            self.line_starts.len()
+        }
+    }
+
+    /// The path of the file this source was read from, if any.
+    pub(crate) fn path(&self) -> Option<&Path> {
+        match &self.st {
+            SourceType::File(f) => Some(Path::new(f)),
+            SourceType::String => None
         }
     }
 }
@@ -80,17 +130,15 @@ pub(crate) struct CodePoint(pub(crate) Rc<Source>, pub(crate) usize);
 
 impl CodePoint {
     #[allow(non_camel_case_types)]
-    type line = usize;
+    pub(crate) type line = usize;
     #[allow(non_camel_case_types)]
-    type index_in_line = usize;
+    pub(crate) type index_in_line = usize;
     pub(crate) fn span(self) -> Span {
         Span::single(self)
     }
 
     pub(crate) fn pos(&self) -> (Self::line, Self::index_in_line){
-        let first_part = &self.0.source[0..self.1];
-        let mut lines_split = first_part.split("\n").collect::<Vec<&str>>();
-        (lines_split.len(), lines_split.pop().unwrap().len())
+        self.0.position(self.1)
     }
 }
 
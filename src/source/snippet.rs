@@ -0,0 +1,162 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
+use crate::source::Source;
+use crate::source::span::Span;
+use crate::util::ansi::{paint, Style};
+
+/// Lines shown before and after every labeled line.
+const CONTEXT_LINES: usize = 1;
+
+/// A location of a diagnostic with an optional message, the first label is the primary one.
+#[derive(Debug, Clone)]
+pub(crate) struct Label {
+    pub(crate) span: Span,
+    pub(crate) text: Option<String>
+}
+
+impl Label {
+    pub(crate) fn new(span: Span) -> Self {
+        Self { span, text: None }
+    }
+
+    pub(crate) fn with_text<T: Into<String>>(span: Span, text: T) -> Self {
+        Self { span, text: Some(text.into()) }
+    }
+}
+
+/// Underline of a label on a single line, `end` is exclusive.
+struct Mark {
+    start: usize,
+    end: usize,
+    primary: bool,
+    style: Style,
+    text: Option<String>
+}
+
+/// Renders the labels grouped by their source, labels close to each other share one snippet.
+/// Notes like `("help", "...")` are appended below, the primary label is drawn in `style`.
+pub(crate) fn render(labels: &[Label], notes: &[(&str, String)], style: Style) -> String {
+    let mut groups: Vec<(Rc<Source>, Vec<(bool, &Label)>)> = vec![];
+    for (i, label) in labels.iter().enumerate() {
+        match groups.iter_mut().find(|(s, _)| Rc::ptr_eq(s, &label.span.source)) {
+            Some((_, group)) => group.push((i == 0, label)),
+            None => groups.push((label.span.source.clone(), vec![(i == 0, label)]))
+        }
+    }
+    let width = labels.iter()
+        .map(|l| (l.span.end().pos().0 + CONTEXT_LINES).to_string().len())
+        .max().unwrap_or(0);
+    let gutter = format!("{} {}", " ".repeat(width), paint("|", Style::Blue));
+    let mut out = vec![];
+    for (i, (source, group)) in groups.iter().enumerate() {
+        let (line, col) = group[0].1.span.start().pos();
+        out.push(format!("{}{} {:?}:{}:{}", " ".repeat(width), paint(if i == 0 { "-->" } else { ":::" }, Style::Blue), source, line, col + 1));
+        out.push(gutter.clone());
+        render_group(source, group, width, &gutter, style, &mut out);
+    }
+    if !labels.is_empty() && !notes.is_empty() {
+        out.push(gutter.clone());
+    }
+    for (kind, text) in notes {
+        out.push(format!("{} {} {}: {}", " ".repeat(width), paint("=", Style::Blue), paint(kind, Style::Bold), text));
+    }
+    out.join("\n")
+}
+
+fn render_group(source: &Source, group: &[(bool, &Label)], width: usize, gutter: &str, style: Style, out: &mut Vec<String>) {
+    let line = |l: usize| source.line(l).unwrap_or("");
+    let line_count = source.line_count();
+    let mut marks: BTreeMap<usize, Vec<Mark>> = BTreeMap::new();
+    for (primary, label) in group {
+        let mark_style = if *primary { style } else { Style::Blue };
+        let (sl, sc) = label.span.start().pos();
+        let (el, ec) = label.span.end().pos();
+        if sl == el {
+            marks.entry(sl).or_default().push(Mark { start: sc, end: ec + 1, primary: *primary, style: mark_style, text: label.text.clone() });
+        } else {
+            // only the first and last line of a multi line span are underlined
+            marks.entry(sl).or_default().push(Mark { start: sc, end: line(sl).chars().count().max(sc + 1), primary: *primary, style: mark_style, text: None });
+            let last = line(el);
+            let indent = (last.chars().count() - last.trim_start().chars().count()).min(ec);
+            marks.entry(el).or_default().push(Mark { start: indent, end: ec + 1, primary: *primary, style: mark_style, text: label.text.clone() });
+        }
+    }
+    let mut shown = marks.keys()
+        .flat_map(|&l| l.saturating_sub(CONTEXT_LINES).max(1)..=(l + CONTEXT_LINES).min(line_count.max(l)))
+        .collect::<BTreeSet<usize>>();
+    // a single skipped line is shown instead of `...`
+    let gaps = shown.iter().zip(shown.iter().skip(1)).filter(|(a, b)| **b == **a + 2).map(|(a, _)| a + 1).collect::<Vec<_>>();
+    shown.extend(gaps);
+    let mut previous = None;
+    for l in shown {
+        if previous.map(|p| l > p + 1).unwrap_or(false) {
+            out.push(paint("...", Style::Blue));
+        }
+        previous = Some(l);
+        out.push(format!("{} {} {}", paint(format!("{l:>width$}"), Style::Blue), paint("|", Style::Blue), line(l))
+            .trim_end().to_string());
+        if let Some(marks) = marks.get_mut(&l) {
+            marks.sort_by_key(|m| (m.start, !m.primary));
+            render_marks(marks, gutter, out);
+        }
+    }
+}
+
+/// Underlines the marks of a line, the rightmost label is written next to the underline,
+/// the others below it connected by `|`.
+fn render_marks(marks: &[Mark], gutter: &str, out: &mut Vec<String>) {
+    let mut underline = String::new();
+    let mut column = 0;
+    // primary marks are drawn last so they stay visible where marks overlap
+    let mut ordered = marks.iter().collect::<Vec<_>>();
+    ordered.sort_by_key(|m| (m.primary, m.start));
+    let mut cells = vec![None; marks.iter().map(|m| m.end).max().unwrap_or(0)];
+    for mark in ordered {
+        for cell in &mut cells[mark.start..mark.end] {
+            *cell = Some(mark);
+        }
+    }
+    let mut i = 0;
+    while i < cells.len() {
+        let Some(mark) = cells[i] else {
+            i += 1;
+            continue
+        };
+        let run = cells[i..].iter().take_while(|c| c.map(|m| std::ptr::eq(m, mark)).unwrap_or(false)).count();
+        underline.push_str(&" ".repeat(i - column));
+        underline.push_str(&paint(if mark.primary { "^" } else { "-" }.repeat(run), mark.style));
+        i += run;
+        column = i;
+    }
+    let mut labeled = marks.iter().filter(|m| m.text.is_some()).collect::<Vec<_>>();
+    let inline = labeled.last()
+        .filter(|m| m.end >= cells.len())
+        .map(|m| format!(" {}", paint(m.text.as_ref().unwrap(), m.style)));
+    if inline.is_some() {
+        labeled.pop();
+    }
+    out.push(format!("{gutter} {underline}{}", inline.unwrap_or_default()));
+    if labeled.is_empty() {
+        return
+    }
+    let bars = |marks: &[&Mark]| {
+        let mut row = String::new();
+        let mut column = 0;
+        for m in marks {
+            if m.start >= column || column == 0 {
+                row.push_str(&" ".repeat(m.start.saturating_sub(column)));
+                row.push_str(&paint("|", m.style));
+                column = m.start + 1;
+            }
+        }
+        (row, column)
+    };
+    out.push(format!("{gutter} {}", bars(&labeled).0));
+    for j in (0..labeled.len()).rev() {
+        let (mut row, column) = bars(&labeled[..j]);
+        let m = labeled[j];
+        row.push_str(&" ".repeat(m.start.saturating_sub(column)));
+        row.push_str(&paint(m.text.as_ref().unwrap(), m.style));
+        out.push(format!("{gutter} {row}"));
+    }
+}
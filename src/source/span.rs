@@ -73,29 +73,6 @@ impl Span {
         self.start = usize::min(self.start, s.start);
         self.end = usize::max(self.end, s.end);
     }
-
-    pub(crate) fn render_span_code(&self, line_pad: usize) -> String {
-        let (sl, sp) = self.start().pos();
-        let (el, ep) = self.end().pos();
-        let lines_split = &self.source.source.split("\n").collect::<Vec<&str>>();
-        let mut render = vec![];
-        for i in usize::max(sl.saturating_sub(line_pad), 1)..=usize::min(el+line_pad, lines_split.len()) {
-            render.push(format!("{i:3} | {}", lines_split[i-1]));
-            if i == sl && i == el {
-                render.push(format!("    | {}{}", " ".repeat(sp), "^".repeat(ep - sp + 1)));
-            }
-            else if i == sl {
-                render.push(format!("    | {}{}", " ".repeat(sp), "^".repeat(lines_split[i-1].len() - sp + 1)));
-            }
-            else if i == el {
-                render.push(format!("    | {}{}", "^".repeat(ep + 1), " ".repeat(lines_split[i-1].len() - ep)));
-            }
-            else if i > sl && i < el {
-                render.push(format!("    | {}", "^".repeat(lines_split[i-1].len())));
-            }
-        }
-        render.join("\n")
-    }
 }
 
 impl Debug for Span {
@@ -0,0 +1,89 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use crate::ast::Module;
+use crate::compiler::Arguments;
+use crate::error::{LithiaError, LithiaET, OnParseErr};
+use crate::source::snippet::{render, Label};
+use crate::source::span::Span;
+use crate::util::ansi::Style;
+
+/// A function tagged with `#[test]`.
+pub(crate) struct TestCase {
+    pub(crate) path: Vec<String>,
+    pub(crate) loc: Span
+}
+
+impl TestCase {
+    pub(crate) fn name(&self) -> String {
+        self.path.join("::")
+    }
+}
+
+/// Collects the tests of the module and all of its sub modules, ordered by their path.
+pub(crate) fn collect_tests(module: &Module) -> Result<Vec<TestCase>, LithiaError> {
+    let mut tests = vec![];
+    collect_module_tests(module, &mut vec![], &mut tests)?;
+    tests.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(tests)
+}
+
+fn collect_module_tests(module: &Module, mod_path: &mut Vec<String>, tests: &mut Vec<TestCase>) -> Result<(), LithiaError> {
+    for (name, func) in &module.functions {
+        if let Some(tag) = func.tags.get("test") {
+            if func.body.is_none() {
+                return Err(LithiaET::TagError("test function needs a body".to_string())
+                    .at_label(func.name.1.clone(), "declared without a body")
+                    .at_label(tag.2.clone(), "tagged as test here"))
+            }
+            tests.push(TestCase {
+                path: mod_path.iter().cloned().chain([name.clone()]).collect(),
+                loc: func.name.1.clone()
+            })
+        }
+    }
+    for (name, sub_module) in &module.sub_modules {
+        mod_path.push(name.clone());
+        collect_module_tests(sub_module, mod_path, tests)?;
+        mod_path.pop();
+    }
+    Ok(())
+}
+
+/// Runs every test of the harness executable in its own process,
+/// so a test which aborts does not take down the others.
+pub(crate) fn run_tests(args: &Arguments, tests: &[TestCase]) -> Result<i32, LithiaError> {
+    let exe = Path::new(".").join(args.exe_path());
+    println!("running {} test{}", tests.len(), if tests.len() == 1 { "" } else { "s" });
+    let mut failures = vec![];
+    for (i, test) in tests.iter().enumerate() {
+        let output = Command::new(&exe).arg(i.to_string()).output()
+            .map_err(LithiaError::from).e_when(format!("running test {}", test.name()))?;
+        if output.status.success() {
+            println!("test {} ... ok", test.name());
+        } else {
+            println!("test {} ... FAILED", test.name());
+            failures.push((test, output));
+        }
+    }
+    if !failures.is_empty() {
+        println!("\nfailures:");
+        for (test, output) in &failures {
+            println!("\n---- {} {} ----\n{}", test.name(), output.status, render(&[Label::new(test.loc.clone())], &[], Style::Red));
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            for captured in [stdout.trim_end(), stderr.trim_end()] {
+                if !captured.is_empty() {
+                    println!("{captured}");
+                }
+            }
+        }
+    }
+    // the harness is only kept if it was asked for by name
+    if args.output.is_none() {
+        fs::remove_file(&exe).map_err(LithiaError::from).e_when(format!("removing {}", exe.display()))?;
+    }
+    println!("\ntest result: {}. {} passed; {} failed", if failures.is_empty() { "ok" } else { "FAILED" },
+             tests.len() - failures.len(), failures.len());
+    Ok(if failures.is_empty() { 0 } else { 1 })
+}
@@ -39,12 +39,17 @@ impl Debug for Token {
 /// `true` if the preceding character is also a particle with no spaces or any kind of separator.
 /// Keep in mind that a lot of characters qualify as a particle so it is not always safe to assume a value.
 pub(crate) type glued = bool;
+#[allow(non_camel_case_types)]
+/// `true` for `//!` doc comments, which document the module they are written in instead of the following item.
+pub(crate) type inner = bool;
 #[derive(Debug, Clone, PartialEq)]
 
 pub(crate) enum TokenType {
     Particle(char, glued),
     Ident(String),
-    Literal(Literal)
+    Literal(Literal),
+    /// a line of a `///` doc comment, or of a `//!` one documenting the surrounding module if `inner`
+    Doc(String, inner)
 }
 
 impl TokenType {
@@ -53,6 +58,22 @@ impl TokenType {
     }
 }
 
+/// How the token is named in parsing errors.
+impl Display for TokenType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenType::Particle(c, _) => write!(f, "`{c}`"),
+            TokenType::Ident(i) => write!(f, "`{i}`"),
+            TokenType::Literal(Literal::Bool(b)) => write!(f, "`{b}`"),
+            TokenType::Literal(Literal::String(_)) => write!(f, "string literal"),
+            TokenType::Literal(Literal::Char(_)) => write!(f, "char literal"),
+            TokenType::Literal(Literal::Number(..)) => write!(f, "number literal"),
+            TokenType::Literal(Literal::Array(..)) => write!(f, "array literal"),
+            TokenType::Doc(..) => write!(f, "doc comment"),
+        }
+    }
+}
+
 #[derive(Clone, PartialEq)]
 pub(crate) enum Literal {
     String(String),
@@ -28,22 +28,45 @@ pub(crate) fn tokenize(source: Source) -> Result<Vec<Token>, LithiaError>{
                 tokens.push(TokenType::Literal(Literal::String(unescape_str(&string, &span)?)).at(span));
             }
             '/' => {
+                let mut start = iter.here();
                 iter.next();
                 let r: Result<(), LithiaError> = try {
                     match iter.this()? {
                         '/' => {
-                            let _comment = collect_until(&mut iter, true, true,
+                            let (comment, span) = collect_until(&mut iter, true, true,
                                                          |iter| Ok(iter.this().map(|c| c != '\n').unwrap_or(false))).e_when("tokenizing single line comment".to_string())?;
+                            // `///` and `//!` are doc comments, but `////` is not
+                            let doc = match comment.chars().next() {
+                                Some('/') if !comment.starts_with("//") => Some((&comment[1..], false)),
+                                Some('!') => Some((&comment[1..], true)),
+                                _ => None
+                            };
+                            if let Some((doc, inner)) = doc {
+                                let doc = doc.trim_end_matches('\r');
+                                start.combine(span);
+                                tokens.push(TokenType::Doc(doc.strip_prefix(' ').unwrap_or(doc).to_string(), inner).at(start));
+                            }
                         },
                         '*' => {
-                            loop {
-                                let _comment = collect_until(&mut iter, true, true,
-                                                             |iter| Ok(iter.this()? != '*')).e_when("tokenizing single line comment".to_string())?;
-                                iter.next();
-                                if iter.this()? == '/' {
-                                    break
+                            // block comments nest, every `/*` needs its own `*/`
+                            let mut depth = 1usize;
+                            iter.next();
+                            while depth > 0 {
+                                match (iter.this(), iter.peek()) {
+                                    (Ok('/'), Ok('*')) => {
+                                        depth += 1;
+                                        iter.next();
+                                    }
+                                    (Ok('*'), Ok('/')) => {
+                                        depth -= 1;
+                                        iter.next();
+                                    }
+                                    (Ok(_), _) => (),
+                                    (Err(_), _) => return Err(LithiaET::EOF.at(start).when("looking for the end of the block comment"))
                                 }
+                                iter.next();
                             }
+                            iter.index -= 1;
                         }
                         _ => { // was just normal division slash or sth other
                             iter.index -= 1;
@@ -58,7 +81,7 @@ pub(crate) fn tokenize(source: Source) -> Result<Vec<Token>, LithiaError>{
             '\'' => {
                 let (char_src, span) = collect_until(&mut iter, true, true,
                                                      |iter| Ok(iter.this()? != '\'')).e_when("tokenizing char literal".to_string())?;
-                if char_src.len() != 1 {
+                if char_src.chars().count() != 1 {
                     return Err(LithiaET::TokenizationError(format!("Expected char, found: '{}'", char_src)).at(span))
                 }
                 let char = char_src.chars().nth(0).unwrap();
@@ -67,9 +90,9 @@ pub(crate) fn tokenize(source: Source) -> Result<Vec<Token>, LithiaError>{
             c if c.is_whitespace() => {
                 // pass
             }
-            c if c.is_ascii_alphabetic() || c == '_' => {
+            c if c.is_alphabetic() || c == '_' => {
                 let (ident, span) = collect_until(&mut iter, false, false,
-                                                  |iter| Ok({let c = iter.this()?; c.is_ascii_alphanumeric() || c == '_'})).e_when("tokenizing identifier".to_string())?;
+                                                  |iter| Ok({let c = iter.this()?; c.is_alphanumeric() || c == '_'})).e_when("tokenizing identifier".to_string())?;
                 tokens.push(match ident {
                     ident if &ident == "true" => TokenType::Literal(Literal::Bool(true)),
                     ident if &ident == "false" => TokenType::Literal(Literal::Bool(false)),
@@ -0,0 +1,37 @@
+use std::env;
+use std::io::{stdout, IsTerminal};
+use std::sync::OnceLock;
+
+/// Styles of the diagnostic output, all of them are bold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Style {
+    Bold,
+    Red,
+    Yellow,
+    Blue
+}
+
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Style::Bold => "1",
+            Style::Red => "1;31",
+            Style::Yellow => "1;33",
+            Style::Blue => "1;34"
+        }
+    }
+}
+
+/// Colors are only used when stdout is a terminal and `NO_COLOR` is not set.
+fn enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| stdout().is_terminal() && env::var_os("NO_COLOR").is_none())
+}
+
+pub(crate) fn paint<S: AsRef<str>>(text: S, style: Style) -> String {
+    if enabled() && !text.as_ref().is_empty() {
+        format!("\x1b[{}m{}\x1b[0m", style.code(), text.as_ref())
+    } else {
+        text.as_ref().to_string()
+    }
+}
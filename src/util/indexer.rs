@@ -1,4 +1,5 @@
 use std::rc::Rc;
+use crate::diagnostics::Diagnostics;
 use crate::error::{LithiaError, LithiaET};
 use crate::source::span::Span;
 
@@ -14,6 +15,35 @@ pub(crate) trait Indexable {
 pub(crate) struct Indexer<T> {
     list: Rc<T>,
     pub(crate) index: usize,
+    /// errors recovered from, dropped together with iterators cloned for lookahead
+    pub(crate) diagnostics: Diagnostics,
+    /// what failed patterns expected, lookaheads merge theirs back with `merge_expected`
+    pub(crate) expected: Expected
+}
+
+/// Descriptions of what was expected at the furthest index anything failed at.
+#[derive(Clone, Default)]
+pub(crate) struct Expected {
+    pub(crate) index: usize,
+    pub(crate) items: Vec<String>
+}
+
+impl Expected {
+    /// Keeps the descriptions of the furthest index, those of the same index are combined.
+    pub(crate) fn merge(&mut self, other: Expected) {
+        if other.items.is_empty() {
+            return
+        }
+        if other.index > self.index || self.items.is_empty() {
+            *self = other
+        } else if other.index == self.index {
+            for item in other.items {
+                if !self.items.contains(&item) {
+                    self.items.push(item)
+                }
+            }
+        }
+    }
 }
 
 impl<T: Indexable> Indexer<T> {
@@ -21,6 +51,8 @@ impl<T: Indexable> Indexer<T> {
         Self {
             list: Rc::new(list),
             index: 0,
+            diagnostics: Diagnostics::new(),
+            expected: Expected::default()
         }
     }
 
@@ -57,6 +89,11 @@ impl<T: Indexable> Indexer<T> {
         self.index += 1;
     }
 
+    /// Takes over what failed in a lookahead on a clone of this iterator.
+    pub(crate) fn merge_expected(&mut self, lookahead: Self) {
+        self.expected.merge(lookahead.expected)
+    }
+
     pub(crate) fn peek(&self) -> Result<T::Item, LithiaError>{
         self.get(self.index + 1)
     }
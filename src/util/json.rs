@@ -0,0 +1,30 @@
+//! Minimal JSON output for machine readable diagnostics.
+
+/// Quotes and escapes the text as a JSON string.
+pub(crate) fn string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c)
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Writes the fields as an object, the values have to be JSON already.
+pub(crate) fn object(fields: &[(&str, String)]) -> String {
+    let fields = fields.iter().map(|(k, v)| format!("{}:{v}", string(k))).collect::<Vec<_>>();
+    format!("{{{}}}", fields.join(","))
+}
+
+pub(crate) fn array<I: IntoIterator<Item=String>>(values: I) -> String {
+    format!("[{}]", values.into_iter().collect::<Vec<_>>().join(","))
+}
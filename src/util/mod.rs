@@ -1 +1,4 @@
+pub(crate) mod ansi;
 pub(crate) mod indexer;
+pub(crate) mod json;
+pub(crate) mod suggest;
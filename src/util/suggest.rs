@@ -0,0 +1,40 @@
+//! Finds names close to a misspelled one for "did you mean" help notes.
+
+/// Number of single char insertions, deletions, substitutions and swaps of neighbouring chars turning `a` into `b`.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    // d[i][j] is the distance between the first i chars of a and the first j chars of b
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for j in 0..=b.len() {
+        d[0][j] = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = (a[i - 1] != b[j - 1]) as usize;
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// The candidate most similar to `name`, a candidate only differing in case is preferred,
+/// otherwise up to a third of the chars may differ. The earliest of equally close candidates wins.
+pub(crate) fn closest<'a, I: IntoIterator<Item=&'a str>>(name: &str, candidates: I) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates.into_iter()
+        .filter(|c| *c != name)
+        .filter_map(|c| if c.to_lowercase() == name.to_lowercase() {
+            Some((0, c))
+        } else {
+            Some((edit_distance(name, c), c)).filter(|(d, _)| *d <= max_distance)
+        })
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c)
+}
@@ -0,0 +1,267 @@
+//! Golden output tests, every `.li` file in the tested directories is compiled and run.
+//!
+//! Expectations are written as comments in the file:
+//! - `// expect: <line>` a line the program prints, in order, alternatively a sibling `<name>.stdout` file
+//! - `// expect-exit: <code>` the exit code of the program, `0` if not given
+//! - `// error: <LithiaET variant>` at the end of each line the compilation has to report an error at
+//! - `// warning: <lint>` at the end of each line a lint has to warn at, other warnings fail the test
+//! - `// ignore: <reason>` skips the file
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const LITHIA: &str = env!("CARGO_BIN_EXE_lithia");
+
+#[derive(Debug, Default)]
+struct Expectations {
+    stdout: Option<String>,
+    exit_code: Option<i32>,
+    errors: Vec<(String, usize)>,
+    warnings: Vec<(String, usize)>,
+    ignore: Option<String>
+}
+
+impl Expectations {
+    fn read(file: &Path) -> Self {
+        let source = fs::read_to_string(file).unwrap();
+        let mut expectations = Self::default();
+        let mut stdout = vec![];
+        for (i, line) in source.lines().enumerate() {
+            let Some((_, comment)) = line.split_once("//") else { continue };
+            let comment = comment.trim();
+            if let Some(expected) = comment.strip_prefix("expect:") {
+                stdout.push(expected.strip_prefix(' ').unwrap_or(expected).to_string());
+            } else if let Some(code) = comment.strip_prefix("expect-exit:") {
+                expectations.exit_code = Some(code.trim().parse().expect("exit code has to be a number"));
+            } else if let Some(kind) = comment.strip_prefix("error:") {
+                expectations.errors.push((kind.trim().to_string(), i + 1));
+            } else if let Some(lint) = comment.strip_prefix("warning:") {
+                expectations.warnings.push((lint.trim().to_string(), i + 1));
+            } else if let Some(reason) = comment.strip_prefix("ignore:") {
+                expectations.ignore = Some(reason.trim().to_string());
+            }
+        }
+        let stdout_file = file.with_extension("stdout");
+        if stdout_file.is_file() {
+            expectations.stdout = Some(fs::read_to_string(stdout_file).unwrap());
+        } else if !stdout.is_empty() {
+            expectations.stdout = Some(stdout.into_iter().map(|l| l + "\n").collect());
+        }
+        expectations
+    }
+}
+
+fn lithia(args: &[&str]) -> Output {
+    Command::new(LITHIA).args(args).output().unwrap()
+}
+
+/// The raw value of the first `key` in a json diagnostic, for spans that is the primary one.
+fn json_value<'a>(diagnostic: &'a str, key: &str) -> Option<&'a str> {
+    let (_, rest) = diagnostic.split_once(&format!("\"{key}\":"))?;
+    rest.split([',', '}']).next()
+}
+
+/// Checks a single file, returns a description of what went wrong.
+fn check_file(file: &Path, out_dir: &Path) -> Result<(), String> {
+    let expectations = Expectations::read(file);
+    if expectations.ignore.is_some() {
+        return Ok(())
+    }
+    if expectations.stdout.is_none() && expectations.exit_code.is_none() && expectations.errors.is_empty() {
+        return Err("no expectations, add `// expect:`, `// error:`, `// ignore:` or a .stdout file".to_string())
+    }
+    let file_str = file.to_string_lossy().to_string();
+    let exe = out_dir.join(file.file_stem().unwrap());
+    let exe_str = exe.to_string_lossy().to_string();
+    if !expectations.errors.is_empty() {
+        let output = lithia(&["build", &file_str, "-o", &exe_str, "--error-format=json"]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if output.status.success() {
+            return Err(format!("expected {} errors, but compilation succeeded", expectations.errors.len()))
+        }
+        let mut errors = stdout.lines().filter(|l| l.contains("\"level\":\"error\"")).collect::<Vec<_>>();
+        for (kind, line) in &expectations.errors {
+            // kind and primary line have to come from the same error, each error is matched once
+            let found = errors.iter().position(|e| json_value(e, "kind") == Some(&format!("\"{kind}\""))
+                && json_value(e, "line_start") == Some(&line.to_string()));
+            match found {
+                Some(i) => { errors.remove(i); },
+                None => return Err(format!("expected {kind} at line {line}, got:\n{stdout}"))
+            }
+        }
+        if !errors.is_empty() {
+            return Err(format!("expected {} errors, got:\n{stdout}", expectations.errors.len()))
+        }
+        return Ok(())
+    }
+    let build = lithia(&["build", &file_str, "-o", &exe_str, "--error-format=json"]);
+    let build_stdout = String::from_utf8_lossy(&build.stdout);
+    if !build.status.success() {
+        return Err(format!("compilation failed:\n{build_stdout}"))
+    }
+    let warnings = build_stdout.lines().filter(|l| l.contains("\"level\":\"warning\"")).collect::<Vec<_>>();
+    for (lint, line) in &expectations.warnings {
+        let found = warnings.iter().any(|w| json_value(w, "lint") == Some(&format!("\"{lint}\""))
+            && json_value(w, "line_start") == Some(&line.to_string()));
+        if !found {
+            return Err(format!("expected {lint} warning at line {line}, got:\n{build_stdout}"))
+        }
+    }
+    if warnings.len() != expectations.warnings.len() {
+        return Err(format!("expected {} warnings, got:\n{build_stdout}", expectations.warnings.len()))
+    }
+    let output = Command::new(&exe).output().map_err(|e| format!("could not run {}: {e}", exe.display()))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if let Some(expected) = &expectations.stdout {
+        if &stdout != expected {
+            return Err(format!("stdout differs\n--- expected\n{expected}--- found\n{stdout}"))
+        }
+    }
+    let code = expectations.exit_code.unwrap_or(0);
+    if output.status.code() != Some(code) {
+        return Err(format!("expected exit code {code}, found {}", output.status))
+    }
+    Ok(())
+}
+
+fn check_dir(dir: &str) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(dir.file_name().unwrap());
+    fs::create_dir_all(&out_dir).unwrap();
+    let mut files = fs::read_dir(&dir).unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().map(|e| e == "li").unwrap_or(false))
+        .collect::<Vec<PathBuf>>();
+    files.sort();
+    assert!(!files.is_empty(), "no .li files in {}", dir.display());
+    let failures = files.iter()
+        .filter_map(|f| check_file(f, &out_dir).err().map(|e| format!("{}: {e}", f.display())))
+        .collect::<Vec<_>>();
+    assert!(failures.is_empty(), "{} of {} files failed:\n\n{}", failures.len(), files.len(), failures.join("\n\n"));
+}
+
+#[test]
+fn examples() {
+    check_dir("examples/testing")
+}
+
+#[test]
+fn compile_errors() {
+    check_dir("examples/testing/errors")
+}
+
+/// Builds `examples/library` as a static library without main and links the C program in it against it.
+#[test]
+fn static_library() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/library");
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("library");
+    fs::create_dir_all(&out_dir).unwrap();
+    let lib = out_dir.join("libcounter.a");
+    let build = lithia(&["build", &dir.join("counter.li").to_string_lossy(), "--crate-type=staticlib", "-o", &lib.to_string_lossy()]);
+    assert!(build.status.success(), "compilation failed:\n{}", String::from_utf8_lossy(&build.stdout));
+    let exe = out_dir.join("main");
+    let cc = std::env::var("CC").unwrap_or("cc".to_string());
+    let link = Command::new(cc).arg(dir.join("main.c")).arg(&lib).arg("-o").arg(&exe).output().unwrap();
+    assert!(link.status.success(), "linking failed:\n{}", String::from_utf8_lossy(&link.stderr));
+    let output = Command::new(&exe).output().unwrap();
+    let expected = Expectations::read(&dir.join("main.c")).stdout.unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+}
+
+/// The human readable rendering of errors and warnings with several labels, gaps, notes and help.
+#[test]
+fn rendered_diagnostics() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/testing/errors");
+    let exe = Path::new(env!("CARGO_TARGET_TMPDIR")).join("rendering");
+    // relative to the file, so the rendered path does not depend on the checkout
+    let output = Command::new(LITHIA).current_dir(&dir).env("NO_COLOR", "1")
+        .args(["build", "rendering.li", "-o", &exe.to_string_lossy()]).output().unwrap();
+    assert!(!output.status.success());
+    let expected = fs::read_to_string(dir.join("rendering.txt")).unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+}
+
+/// Every error code has an explanation, unknown codes are rejected.
+#[test]
+fn explain_error_codes() {
+    for i in 1..=18 {
+        let code = format!("L{i:04}");
+        let output = lithia(&["--explain", &code]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success() && stdout.starts_with(&format!("# {code}: ")), "no explanation for {code}:\n{stdout}");
+    }
+    assert!(!lithia(&["--explain", "L9999"]).status.success());
+}
+
+/// Misspelled names get a help note naming the close match.
+#[test]
+fn suggestions() {
+    let file = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/testing/errors/misspelled_names.li");
+    let output = lithia(&["build", &file.to_string_lossy(), "-o", &Path::new(env!("CARGO_TARGET_TMPDIR")).join("misspelled_names").to_string_lossy()]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for help in [
+        "help: make it mutable: let mut counter",
+        "help: a variable with a similar name exists: value",
+        "help: an item with a similar name exists: area",
+        "help: a type with a similar name exists: i32",
+        "help: a tag with a similar name exists: unsafe"
+    ] {
+        assert!(stdout.contains(help), "expected {help}, got:\n{stdout}");
+    }
+}
+
+/// Doc comments are attached to the items and modules they document and printed with `--emit ast`,
+/// elsewhere they are ordinary comments.
+#[test]
+fn doc_comments() {
+    let file = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/testing/doc_comments.li");
+    let ast = Path::new(env!("CARGO_TARGET_TMPDIR")).join("doc_comments.ast");
+    let output = lithia(&["build", &file.to_string_lossy(), "--emit", "ast", "-o", &ast.to_string_lossy()]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    let ast = fs::read_to_string(ast).unwrap();
+    for doc in [
+        "/// Doc comments are kept as `doc` tags of the items they document,\n/// `--emit ast` prints them again.\nmod doc_comments {",
+        "/// Formats and prints to stdout.\n",
+        "/// Doubles `x`.\n    ///\n    /// Overflows like any other multiplication.\n    fn double(x: u32) -> u32",
+        "/// Functions on numbers.\n    /// Everything in here is pure.\n    mod math {",
+        "/// The square of `x`.\n        fn square(x: u32) -> u32"
+    ] {
+        assert!(ast.contains(doc), "expected {doc}, got:\n{ast}");
+    }
+    for comment in ["four slashes", "anything but", "like this one", "inner ones", "closing brace", "end of the file"] {
+        assert!(!ast.contains(comment), "expected {comment} to be an ordinary comment, got:\n{ast}");
+    }
+}
+
+/// `--error-format=json` writes one object per error with the location of the error.
+#[test]
+fn json_errors() {
+    let file = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/testing/errors/parse_recovery.li");
+    let file_str = file.to_string_lossy().to_string();
+    let expectations = Expectations::read(&file);
+    let output = lithia(&["check", &file_str, "--error-format=json"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), expectations.errors.len(), "expected one line per error, got:\n{stdout}");
+    for (line, (kind, line_nr)) in lines.iter().zip(&expectations.errors) {
+        assert!(line.starts_with(&format!("{{\"kind\":\"{kind}\",")), "expected {kind}, got:\n{line}");
+        assert!(line.contains(&format!("\"line_start\":{line_nr},")), "expected line {line_nr}, got:\n{line}");
+    }
+    // the alternatives of all branches are listed in a single error
+    let expected = "\"message\":\"expected one of `#`, `fn`, `const`, `mod`, `use` — found `}`\"";
+    assert!(lines.last().unwrap().contains(expected), "expected {expected}, got:\n{stdout}");
+}
+
+/// Columns count chars while byte offsets count the UTF-8 encoding, both differ after non-ASCII text.
+#[test]
+fn unicode_positions() {
+    let file = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/testing/errors/unicode_column.li");
+    let output = lithia(&["build", &file.to_string_lossy(), "-o", &Path::new(env!("CARGO_TARGET_TMPDIR")).join("unicode_column").to_string_lossy(), "--error-format=json"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success());
+    // `unbekannt` is the 30th char of line 3, `ü`, `ß` and `✓` before it take 4 more bytes than chars
+    assert_eq!(json_value(&stdout, "column_start"), Some("30"), "got:\n{stdout}");
+    assert_eq!(json_value(&stdout, "byte_start"), Some("99"), "got:\n{stdout}");
+}